@@ -0,0 +1,156 @@
+use std::time::{Duration, Instant};
+
+use crate::controller::{self, Controller};
+use crate::input_sequence::{ActionSuccess, ControllerAction, InputSequence, InputSequenceState};
+use crate::zones::{self, TriggerZone, Zone, ZoneTrait};
+
+/// Sequence name, every action with the time since the one before it and how
+/// well it was timed, and the chance of success.
+pub type CompletedSequence = (&'static str, Vec<(ControllerAction, Duration, ActionSuccess)>, f64);
+
+/// Turns controller states into actions and matches them against the input
+/// sequences. Doesn't draw anything, so the window and headless mode share it.
+pub struct SequenceTracker<'a> {
+    pub input_sequences_states: Vec<InputSequenceState<'a>>,
+    //zone, in it last poll
+    stick_zones: Vec<(Zone, bool)>,
+    c_stick_zones: Vec<(Zone, bool)>,
+    //zone, (in it with L last poll, in it with R last poll)
+    trigger_zones: Vec<(TriggerZone, (bool, bool))>,
+}
+
+/// The new zones without repeats, keeping what was known about the ones that were already there.
+fn keep_state<Z: PartialEq, S: Copy + Default>(old: &[(Z, S)], zones: impl IntoIterator<Item = Z>) -> Vec<(Z, S)> {
+    let mut new: Vec<(Z, S)> = vec![];
+    for zone in zones {
+        if new.iter().any(|(z, _)| *z == zone) {
+            continue
+        }
+        let state = old.iter().find(|(z, _)| *z == zone).map(|(_, state)| *state).unwrap_or_default();
+        new.push((zone, state));
+    }
+    new
+}
+
+impl<'a> SequenceTracker<'a> {
+    pub fn new(sequences: &'a [InputSequence]) -> Self {
+        let mut tracker = Self {
+            input_sequences_states: sequences.iter().map(InputSequenceState::new).collect(),
+            stick_zones: vec![],
+            c_stick_zones: vec![],
+            trigger_zones: vec![],
+        };
+        tracker.set_zones(zones::get_some_zones(), zones::get_some_zones(), zones::get_some_trigger_zones());
+        tracker
+    }
+
+    /// Which zones to make `Enter`/`Leave` actions for, on top of the ones the sequences need.
+    /// Usually every zone in the planes the sticks are drawn with.
+    pub fn set_zones(&mut self, stick: Vec<Zone>, c_stick: Vec<Zone>, triggers: Vec<TriggerZone>) {
+        let (sequence_stick, sequence_c_stick, sequence_triggers) = self.sequence_zones();
+        self.stick_zones = keep_state(&self.stick_zones, stick.into_iter().chain(sequence_stick));
+        self.c_stick_zones = keep_state(&self.c_stick_zones, c_stick.into_iter().chain(sequence_c_stick));
+        self.trigger_zones = keep_state(&self.trigger_zones, triggers.into_iter().chain(sequence_triggers));
+    }
+
+    /// Every zone an action in a sequence mentions, for the stick, c stick and triggers.
+    pub fn sequence_zones(&self) -> (Vec<Zone>, Vec<Zone>, Vec<TriggerZone>) {
+        let (mut stick, mut c_stick, mut triggers) = (vec![], vec![], vec![]);
+        for state in &self.input_sequences_states {
+            for action in state.sequence.actions() {
+                match action {
+                    ControllerAction::Enter(zone) | ControllerAction::Leave(zone) => stick.push(zone.clone()),
+                    ControllerAction::CEnter(zone) | ControllerAction::CLeave(zone) => c_stick.push(zone.clone()),
+                    ControllerAction::LEnter(zone) | ControllerAction::LLeave(zone)
+                        | ControllerAction::REnter(zone) | ControllerAction::RLeave(zone) => triggers.push(*zone),
+                    _ => {}
+                }
+            }
+        }
+        (stick, c_stick, triggers)
+    }
+
+    /// Everything that happened between the controller's last poll and this one.
+    pub fn actions(&mut self, controller: &Controller) -> Vec<ControllerAction> {
+        let stick_pos = controller.stick_pos();
+        let clamp_pos = controller::clamp(stick_pos.0, stick_pos.1);
+        let c_stick_pos = controller.c_stick_pos();
+        let c_clamp_pos = controller::clamp(c_stick_pos.0, c_stick_pos.1);
+
+        let mut actions = vec![];
+        actions.extend(
+            controller.buttons_just_pressed().iter().map(|button| ControllerAction::Press(**button))
+        );
+        actions.extend(
+            controller.buttons_just_released().iter().map(|button| ControllerAction::Release(**button))
+        );
+        for (zone, in_last) in self.stick_zones.iter_mut() {
+            let in_zone = zone.in_zone(clamp_pos);
+            if in_zone && !*in_last {
+                actions.push(ControllerAction::Enter(zone.clone()));
+            }
+            if !in_zone && *in_last {
+                actions.push(ControllerAction::Leave(zone.clone()));
+            }
+            *in_last = in_zone;
+        }
+        for (zone, in_last) in self.c_stick_zones.iter_mut() {
+            let in_zone = zone.in_zone(c_clamp_pos);
+            if in_zone && !*in_last {
+                actions.push(ControllerAction::CEnter(zone.clone()));
+            }
+            if !in_zone && *in_last {
+                actions.push(ControllerAction::CLeave(zone.clone()));
+            }
+            *in_last = in_zone;
+        }
+
+        let l_digital = controller.is_down(&controller::L_BUTTON);
+        let r_digital = controller.is_down(&controller::R_BUTTON);
+        for (zone, (l_in_last, r_in_last)) in self.trigger_zones.iter_mut() {
+            let l_in_zone = zone.in_zone(controller.l_analog(), l_digital);
+            if l_in_zone && !*l_in_last {
+                actions.push(ControllerAction::LEnter(*zone));
+            }
+            if !l_in_zone && *l_in_last {
+                actions.push(ControllerAction::LLeave(*zone));
+            }
+            let r_in_zone = zone.in_zone(controller.r_analog(), r_digital);
+            if r_in_zone && !*r_in_last {
+                actions.push(ControllerAction::REnter(*zone));
+            }
+            if !r_in_zone && *r_in_last {
+                actions.push(ControllerAction::RLeave(*zone));
+            }
+            *l_in_last = l_in_zone;
+            *r_in_last = r_in_zone;
+        }
+        actions
+    }
+
+    /// Feeds the actions to every sequence, returns the ones that finished.
+    pub fn judge(&mut self, actions: &[ControllerAction], controller: &Controller, time: Instant) -> Vec<CompletedSequence> {
+        let mut completed = vec![];
+        for seq in self.input_sequences_states.iter_mut() {
+            for action in actions {
+                let finished = seq.action(action.clone(), controller, time);
+                if finished {
+                    if let Some(actions) = seq.sequence_info() {
+                        let rate = seq.success_rate().unwrap_or(0.);
+                        completed.push((seq.sequence.name(), actions, rate));
+                    }
+                }
+            }
+        }
+        completed
+    }
+
+    pub fn update(&mut self, controller: &Controller, time: Instant) -> Vec<CompletedSequence> {
+        let actions = self.actions(controller);
+        self.judge(&actions, controller, time)
+    }
+}
+
+pub fn has_miss(sequence: &CompletedSequence) -> bool {
+    sequence.1.iter().any(|(_, _, success)| matches!(success, ActionSuccess::EarlyMiss | ActionSuccess::LateMiss))
+}
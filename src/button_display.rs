@@ -1,6 +1,8 @@
 use ggez::{Context, GameResult, graphics::{self, Color, DrawMode, DrawParam, Mesh, Text}};
 
-use crate::{button_scope::BUTTON_COLORS, controller::{self, Controller}};
+use gcviewer::controller::{self, Controller};
+
+use crate::button_scope::BUTTON_COLORS;
 
 type Layout = [(controller::Button, char, [f32; 2], usize); 8];
 
@@ -2,7 +2,9 @@ use std::time::{Duration, Instant};
 
 use ggez::{Context, GameResult, graphics::{self, Color, DrawMode, DrawParam, Mesh}};
 
-use crate::{controller::{BUTTONS, Controller}, oscilloscope::{Scope, ScopeCanvas, ScopeDirection}, reset_graphics};
+use gcviewer::controller::{BUTTONS, Controller};
+
+use crate::{oscilloscope::{Scope, ScopeCanvas, ScopeDirection}, window::reset_graphics};
 
 pub struct ButtonScope {
     scope_canvas: ScopeCanvas,
@@ -0,0 +1,221 @@
+use std::fs;
+
+use crate::controller::{self, Controller};
+
+/// Furthest a stick has been seen from its origin on each axis.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct StickRange {
+    pub min: (i8, i8),
+    pub max: (i8, i8),
+}
+
+impl StickRange {
+    pub fn widen(&mut self, pos: (i8, i8)) {
+        self.min = (self.min.0.min(pos.0), self.min.1.min(pos.1));
+        self.max = (self.max.0.max(pos.0), self.max.1.max(pos.1));
+    }
+}
+
+/// Where the sticks and triggers rest, and how far they've been seen to go.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct Calibration {
+    pub stick_origin: (i8, i8),
+    pub c_stick_origin: (i8, i8),
+    pub l_origin: u8,
+    pub r_origin: u8,
+    pub stick_range: StickRange,
+    pub c_stick_range: StickRange,
+    pub l_max: u8,
+    pub r_max: u8,
+}
+
+impl Calibration {
+    /// Takes the origins from a controller's raw inputs, like the console does on plug in.
+    pub fn from_buffer(buffer: &[u8; 8]) -> Calibration {
+        Calibration {
+            stick_origin: ((buffer[2] as i8).wrapping_sub(-128), (buffer[3] as i8).wrapping_sub(-128)),
+            c_stick_origin: ((buffer[4] as i8).wrapping_sub(-128), (buffer[5] as i8).wrapping_sub(-128)),
+            l_origin: buffer[6],
+            r_origin: buffer[7],
+            ..Default::default()
+        }
+    }
+}
+
+/// X+Y+Start, the same combo that makes the console recentre.
+pub fn is_calibrate_combo(controller: &Controller) -> bool {
+    controller.is_down(&controller::X_BUTTON) && controller.is_down(&controller::Y_BUTTON)
+        && controller.just_pressed(&controller::START_BUTTON)
+}
+
+/// Recentres a controller, then widens the ranges while the sticks are swept
+/// round the rim and the triggers are pressed all the way in.
+pub struct Calibrator {
+    pub controller: usize,
+    pub previous: Calibration,
+    calibration: Calibration,
+}
+
+impl Calibrator {
+    pub fn start(index: usize, controller: &mut Controller) -> Calibrator {
+        let previous = controller.calibration();
+        let calibration = Calibration::from_buffer(&controller.buffer);
+        controller.set_calibration(calibration);
+        Calibrator { controller: index, previous, calibration }
+    }
+
+    pub fn update(&mut self, controller: &Controller) {
+        self.calibration.stick_range.widen(controller.stick_pos());
+        self.calibration.c_stick_range.widen(controller.c_stick_pos());
+        self.calibration.l_max = self.calibration.l_max.max(controller.l_analog());
+        self.calibration.r_max = self.calibration.r_max.max(controller.r_analog());
+    }
+
+    pub fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+}
+
+/*
+profile file layout, one section per profile, ranges are min x, max x, min y, max y
+and triggers are origin then max:
+    [port1]
+    stick_origin 2 -1
+    stick_range -76 78 -77 75
+    c_stick_origin 0 3
+    c_stick_range -70 71 -72 70
+    l_trigger 31 190
+    r_trigger 28 194
+*/
+pub struct ProfileStore {
+    pub path: String,
+    pub profiles: Vec<(String, Calibration)>,
+    //one profile for every port, instead of port1, port2...
+    pub name: Option<String>,
+}
+
+impl ProfileStore {
+    /// A missing file is just an empty store, it gets created on the first save.
+    pub fn load(path: &str) -> Result<ProfileStore, Box<dyn std::error::Error>> {
+        let profiles = match fs::read_to_string(path) {
+            Ok(text) => parse_profiles(&text)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => Err(err)?,
+        };
+        Ok(ProfileStore { path: path.to_string(), profiles, name: None })
+    }
+
+    /// The picked name, or port1..port4 to match the port labels.
+    pub fn profile_name(&self, index: usize) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("port{}", index + 1),
+        }
+    }
+
+    /// A controller for this port, with its profile's calibration if there is one.
+    pub fn new_controller(&self, index: usize) -> Controller {
+        let mut controller = Controller::new();
+        if let Some(calibration) = self.get(&self.profile_name(index)) {
+            controller.set_calibration(calibration);
+        }
+        controller
+    }
+
+    pub fn get(&self, name: &str) -> Option<Calibration> {
+        self.profiles.iter().find(|(profile, _)| profile == name).map(|(_, calibration)| *calibration)
+    }
+
+    pub fn set(&mut self, name: &str, calibration: Calibration) {
+        match self.profiles.iter_mut().find(|(profile, _)| profile == name) {
+            Some(profile) => profile.1 = calibration,
+            None => self.profiles.push((name.to_string(), calibration)),
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        fs::write(&self.path, profiles_to_string(&self.profiles))
+    }
+}
+
+pub fn profiles_to_string(profiles: &[(String, Calibration)]) -> String {
+    let mut text = String::new();
+    for (name, calibration) in profiles {
+        let range = |range: StickRange| format!("{} {} {} {}", range.min.0, range.max.0, range.min.1, range.max.1);
+        text += &format!("[{}]\n", name);
+        text += &format!("stick_origin {} {}\n", calibration.stick_origin.0, calibration.stick_origin.1);
+        text += &format!("stick_range {}\n", range(calibration.stick_range));
+        text += &format!("c_stick_origin {} {}\n", calibration.c_stick_origin.0, calibration.c_stick_origin.1);
+        text += &format!("c_stick_range {}\n", range(calibration.c_stick_range));
+        text += &format!("l_trigger {} {}\n", calibration.l_origin, calibration.l_max);
+        text += &format!("r_trigger {} {}\n", calibration.r_origin, calibration.r_max);
+    }
+    text
+}
+
+pub fn parse_profiles(text: &str) -> Result<Vec<(String, Calibration)>, String> {
+    let mut profiles: Vec<(String, Calibration)> = Vec::new();
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue
+        }
+        let bad_line = || format!("bad calibration profile line {}: {}", line_number + 1, line);
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            profiles.push((name.to_string(), Calibration::default()));
+            continue
+        }
+        let (_, calibration) = profiles.last_mut().ok_or_else(bad_line)?;
+        let mut words = line.split_whitespace();
+        let key = words.next().ok_or_else(bad_line)?;
+        let values = words.map(|word| word.parse::<i16>()).collect::<Result<Vec<_>, _>>().map_err(|_| bad_line())?;
+        //out of range values would wrap round instead of failing
+        let stick = |value: &i16| i8::try_from(*value).map_err(|_| bad_line());
+        let trigger = |value: &i16| u8::try_from(*value).map_err(|_| bad_line());
+        match (key, values.as_slice()) {
+            ("stick_origin", [x, y]) => calibration.stick_origin = (stick(x)?, stick(y)?),
+            ("c_stick_origin", [x, y]) => calibration.c_stick_origin = (stick(x)?, stick(y)?),
+            ("stick_range", [min_x, max_x, min_y, max_y]) => calibration.stick_range = StickRange { min: (stick(min_x)?, stick(min_y)?), max: (stick(max_x)?, stick(max_y)?) },
+            ("c_stick_range", [min_x, max_x, min_y, max_y]) => calibration.c_stick_range = StickRange { min: (stick(min_x)?, stick(min_y)?), max: (stick(max_x)?, stick(max_y)?) },
+            ("l_trigger", [origin, max]) => {
+                calibration.l_origin = trigger(origin)?;
+                calibration.l_max = trigger(max)?;
+            }
+            ("r_trigger", [origin, max]) => {
+                calibration.r_origin = trigger(origin)?;
+                calibration.r_max = trigger(max)?;
+            }
+            _ => Err(bad_line())?,
+        }
+    }
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_survive_the_file() {
+        let calibration = Calibration {
+            stick_origin: (2, -1),
+            c_stick_origin: (-128, 127),
+            l_origin: 31,
+            r_origin: 0,
+            stick_range: StickRange { min: (-76, -77), max: (78, 75) },
+            c_stick_range: StickRange { min: (-70, -72), max: (71, 70) },
+            l_max: 190,
+            r_max: 255,
+        };
+        let profiles = vec![("port1".to_string(), calibration), ("mine".to_string(), Calibration::default())];
+        assert_eq!(parse_profiles(&profiles_to_string(&profiles)), Ok(profiles));
+    }
+
+    #[test]
+    fn out_of_range_values_are_errors() {
+        for line in ["stick_origin 200 0", "c_stick_range -129 0 0 0", "l_trigger 31 256", "r_trigger -1 194"] {
+            let err = parse_profiles(&format!("[port1]\n{}\n", line)).unwrap_err();
+            assert!(err.contains("line 2"), "{}", err);
+        }
+    }
+}
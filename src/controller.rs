@@ -1,5 +1,7 @@
 use std::convert::TryInto;
 
+use crate::calibration::Calibration;
+
 /*
 //ty altimor!!
 let magnitude = Math.sqrt(x*x + y*y);
@@ -20,16 +22,72 @@ pub fn clamp(x_in: i8, y_in: i8) -> (i8, i8) {
     return ((x*scale).trunc() as i8, (y*scale).trunc() as i8)
 }
 
+//22 units (.2750) and under is dead, 23 (.2875) is the first value out
+pub const DEADZONE: i8 = 23;
+//melee's stick floats go up in steps of .0125, so 80 units is 1.0
+pub const MELEE_UNIT: f32 = 0.0125;
+
+/// Snaps each axis to 0 separately, after clamping like the game does.
+pub fn deadzone(x: i8, y: i8) -> (i8, i8) {
+    let snap = |v: i8| if v.unsigned_abs() < DEADZONE as u8 { 0 } else { v };
+    (snap(x), snap(y))
+}
+
+pub fn to_melee_float(x: i8, y: i8) -> (f32, f32) {
+    (x as f32 * MELEE_UNIT, y as f32 * MELEE_UNIT)
+}
+
+/// Steps from the adapter's byte to the value the game uses, each one
+/// applied on top of the one before.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum StickStage {
+    //the adapter byte, centred on 0
+    Raw,
+    //minus the origin
+    Origin,
+    //pulled into the radius 80 circle
+    Clamped,
+    //with the deadzone snapped to 0
+    Deadzone,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ControllerType {
+    Disconnected,
+    Wired,
+    Wireless,
+}
+
+/// Decoded from the byte before each port's inputs in the adapter's buffer.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct PortStatus {
+    pub controller_type: ControllerType,
+    pub rumble_power: bool,
+}
+
+impl PortStatus {
+    pub fn from_byte(status: u8) -> PortStatus {
+        let controller_type = match status >> 4 {
+            1 => ControllerType::Wired,
+            2 => ControllerType::Wireless,
+            _ => ControllerType::Disconnected,
+        };
+        PortStatus { controller_type, rumble_power: status & 0x04 != 0 }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.controller_type != ControllerType::Disconnected
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Controller {
     pub buffer: [u8; 8],
     buffer_last: [u8; 8],
-    startx: i8,
-    starty: i8,
-    c_startx: i8,
-    c_starty: i8,
-    l_start: u8,
-    r_start: u8,
+    status: PortStatus,
+    calibration: Calibration,
+    //set once a calibration is picked on purpose, so the first packet doesn't overwrite it
+    calibrated: bool,
 }
 
 #[derive(PartialEq, Clone, Copy, Debug)]
@@ -64,24 +122,36 @@ pub const BUTTONS: [Button; 12] = [A_BUTTON, B_BUTTON, X_BUTTON, Y_BUTTON, D_LEF
 
 impl Controller {
     pub fn new() -> Controller {
-        let c = Controller { buffer: [0; 8], buffer_last: [0; 8], startx: 0, starty: 0, c_startx: 0, c_starty: 0, l_start: 0, r_start: 0 };
+        let c = Controller { buffer: [0; 8], buffer_last: [0; 8], status: PortStatus::from_byte(0), calibration: Calibration::default(), calibrated: false };
         c
     }
 
     pub fn from_buffer(&mut self, buffer: &[u8; 8]) {
-        if self.buffer[2..6] == [0; 4] && buffer[2..6] != [0; 4] {
-            self.startx = (buffer[2] as i8).wrapping_sub(-128);
-            self.starty = (buffer[3] as i8).wrapping_sub(-128);
-            self.c_startx = (buffer[4] as i8).wrapping_sub(-128);
-            self.c_starty = (buffer[5] as i8).wrapping_sub(-128);
-            self.l_start = buffer[6];
-            self.r_start = buffer[7];
-            //println!("setting start pos's {} {} {} {}", self.startx, self.starty, self.c_startx, self.c_starty);
+        if !self.calibrated && self.buffer[2..6] == [0; 4] && buffer[2..6] != [0; 4] {
+            self.calibration = Calibration::from_buffer(buffer);
         }
         self.buffer_last = self.buffer;
         self.buffer.copy_from_slice(buffer);
     }
 
+    pub fn set_status(&mut self, status: u8) {
+        self.status = PortStatus::from_byte(status);
+    }
+
+    pub fn status(&self) -> PortStatus {
+        self.status
+    }
+
+    pub fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    /// Use these origins instead of the ones from the first packet.
+    pub fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+        self.calibrated = true;
+    }
+
     pub fn buttons_just_pressed(&self) -> Vec<&Button> {
         let mut buttons = vec!{};
         for button in BUTTONS.iter() {
@@ -129,16 +199,16 @@ impl Controller {
     pub fn stick_pos(&self) -> (i8, i8) {
         let (x, y) = self.stick_raw();
         (
-            x.saturating_sub(self.startx),
-            y.saturating_sub(self.starty)
+            x.saturating_sub(self.calibration.stick_origin.0),
+            y.saturating_sub(self.calibration.stick_origin.1)
         )
     }
 
     pub fn c_stick_pos(&self) -> (i8, i8) {
         let (x, y) = self.c_stick_raw();
         (
-            x.saturating_sub(self.c_startx),
-            y.saturating_sub(self.c_starty)
+            x.saturating_sub(self.calibration.c_stick_origin.0),
+            y.saturating_sub(self.calibration.c_stick_origin.1)
         )
     }
 
@@ -152,6 +222,45 @@ impl Controller {
         clamp(pos.0, pos.1)
     }
 
+    pub fn stick_deadzone(&self) -> (i8, i8) {
+        let pos = self.stick_clamp();
+        deadzone(pos.0, pos.1)
+    }
+
+    pub fn c_stick_deadzone(&self) -> (i8, i8) {
+        let pos = self.c_stick_clamp();
+        deadzone(pos.0, pos.1)
+    }
+
+    /// What the game sees, -1.0 to 1.0.
+    pub fn stick_melee(&self) -> (f32, f32) {
+        let pos = self.stick_deadzone();
+        to_melee_float(pos.0, pos.1)
+    }
+
+    pub fn c_stick_melee(&self) -> (f32, f32) {
+        let pos = self.c_stick_deadzone();
+        to_melee_float(pos.0, pos.1)
+    }
+
+    pub fn stick_at(&self, stage: StickStage) -> (i8, i8) {
+        match stage {
+            StickStage::Raw => self.stick_raw(),
+            StickStage::Origin => self.stick_pos(),
+            StickStage::Clamped => self.stick_clamp(),
+            StickStage::Deadzone => self.stick_deadzone(),
+        }
+    }
+
+    pub fn c_stick_at(&self, stage: StickStage) -> (i8, i8) {
+        match stage {
+            StickStage::Raw => self.c_stick_raw(),
+            StickStage::Origin => self.c_stick_pos(),
+            StickStage::Clamped => self.c_stick_clamp(),
+            StickStage::Deadzone => self.c_stick_deadzone(),
+        }
+    }
+
     pub fn stick_raw(&self) -> (i8, i8) {
         ((self.buffer[2] as i8).wrapping_sub(-128), (self.buffer[3] as i8).wrapping_sub(-128))
     }
@@ -161,19 +270,19 @@ impl Controller {
     }
 
     pub fn l_analog(&self) -> u8 {
-        self.buffer[6].saturating_sub(self.l_start)
+        self.buffer[6].saturating_sub(self.calibration.l_origin)
     }
 
     pub fn r_analog(&self) -> u8 {
-        self.buffer[7].saturating_sub(self.r_start)
+        self.buffer[7].saturating_sub(self.calibration.r_origin)
     }
 
     pub fn l_analog_last(&self) -> u8 {
-        self.buffer_last[6].saturating_sub(self.l_start)
+        self.buffer_last[6].saturating_sub(self.calibration.l_origin)
     }
 
     pub fn r_analog_last(&self) -> u8 {
-        self.buffer_last[7].saturating_sub(self.r_start)
+        self.buffer_last[7].saturating_sub(self.calibration.r_origin)
     }
 }
 
@@ -189,6 +298,7 @@ impl ToString for Controller {
 pub fn update_controllers(controllers: &mut [Controller], buffer: &[u8; 37]) {
     let mut index = 2;
     for controller in controllers {
+        controller.set_status(buffer[index - 1]);
         controller.from_buffer(&buffer[index..(index+8)].try_into().unwrap());//TODO unwrap
         index += 9;
     }
@@ -0,0 +1,157 @@
+use crate::controller;
+
+//one bin per degree, going anticlockwise from straight right
+const BINS: usize = 360;
+//anything closer to the middle than this isn't the stick touching the gate
+const MIN_MAGNITUDE: f64 = 40.;
+//a notch has to be the furthest point this many degrees either side
+const WINDOW: usize = 12;
+//and stick out this much past the lowest point around it
+const PROMINENCE: f64 = 1.5;
+//how far off a notch can be from a target and still be compared to it
+const TARGET_ANGLE: f64 = 8.;
+
+pub struct NotchTarget {
+    pub name: &'static str,
+    pub pos: (i8, i8),
+}
+
+const fn target(name: &'static str, x: i8, y: i8) -> NotchTarget {
+    NotchTarget { name, pos: (x, y) }
+}
+
+/// The spots people usually notch for, in stick units (.0125 each).
+pub const NOTCH_TARGETS: [NotchTarget; 18] = [
+    target("right", 80, 0),
+    target("up", 0, 80),
+    target("left", -80, 0),
+    target("down", 0, -80),
+    target("up right", 56, 56),
+    target("up left", -56, 56),
+    target("down left", -56, -56),
+    target("down right", 56, -56),
+    //shield drop, down enough to drop through platforms without spot dodging
+    target("shield drop right", 56, -55),
+    target("shield drop left", -56, -55),
+    //the shallowest and steepest angles firefox/firebird can go
+    target("firefox right shallow up", 76, 23),
+    target("firefox right steep up", 23, 76),
+    target("firefox left shallow up", -76, 23),
+    target("firefox left steep up", -23, 76),
+    target("firefox left shallow down", -76, -23),
+    target("firefox left steep down", -23, -76),
+    target("firefox right shallow down", 76, -23),
+    target("firefox right steep down", 23, -76),
+];
+
+fn angle_of(pos: (i8, i8)) -> f64 {
+    (pos.1 as f64).atan2(pos.0 as f64).to_degrees().rem_euclid(360.)
+}
+
+fn magnitude(pos: (i8, i8)) -> f64 {
+    ((pos.0 as f64).powi(2) + (pos.1 as f64).powi(2)).sqrt()
+}
+
+fn angle_between(a: f64, b: f64) -> f64 {
+    let diff = (a - b).rem_euclid(360.);
+    diff.min(360. - diff)
+}
+
+pub struct Notch {
+    /// Degrees anticlockwise from straight right.
+    pub angle: f64,
+    /// Origin relative, before clamping.
+    pub pos: (i8, i8),
+    pub target: Option<&'static NotchTarget>,
+}
+
+impl Notch {
+    /// The notch as the game sees it.
+    pub fn melee(&self) -> (f32, f32) {
+        let (x, y) = controller::clamp(self.pos.0, self.pos.1);
+        let (x, y) = controller::deadzone(x, y);
+        controller::to_melee_float(x, y)
+    }
+
+    /// Clamped position minus the target's, in stick units.
+    pub fn target_offset(&self) -> Option<(i8, i8)> {
+        let target = self.target?;
+        let (x, y) = controller::clamp(self.pos.0, self.pos.1);
+        Some((x.saturating_sub(target.pos.0), y.saturating_sub(target.pos.1)))
+    }
+}
+
+/// The furthest the stick has gone at every angle, which traces out the
+/// gate when the stick is spun round the rim.
+pub struct GateMap {
+    bins: [Option<(i8, i8)>; BINS],
+}
+
+impl GateMap {
+    pub fn new() -> Self {
+        Self { bins: [None; BINS] }
+    }
+
+    pub fn record(&mut self, pos: (i8, i8)) {
+        let radius = magnitude(pos);
+        if radius < MIN_MAGNITUDE {
+            return
+        }
+        let bin = (angle_of(pos) as usize).min(BINS - 1);
+        match self.bins[bin] {
+            Some(old) if magnitude(old) >= radius => {}
+            _ => self.bins[bin] = Some(pos),
+        }
+    }
+
+    /// Every bin's furthest point, to draw the outline with.
+    pub fn outline(&self) -> impl Iterator<Item = (i8, i8)> + '_ {
+        self.bins.iter().flatten().copied()
+    }
+
+    /// How much of the way round the gate has been traced, 0 to 1.
+    pub fn coverage(&self) -> f64 {
+        self.bins.iter().flatten().count() as f64 / BINS as f64
+    }
+
+    /// Local maxima of the gate, each matched up with the closest target.
+    pub fn notches(&self) -> Vec<Notch> {
+        let mut notches = vec![];
+        for (i, pos) in self.bins.iter().enumerate() {
+            let pos = match pos {
+                Some(pos) => *pos,
+                None => continue,
+            };
+            let radius = magnitude(pos);
+            let before = (1..=WINDOW).filter_map(|d| self.bins[(i + BINS - d) % BINS]).map(magnitude);
+            let after = (1..=WINDOW).filter_map(|d| self.bins[(i + d) % BINS]).map(magnitude);
+            let before: Vec<f64> = before.collect();
+            let after: Vec<f64> = after.collect();
+            //needs enough of the gate around it to know it sticks out
+            if before.len() + after.len() < WINDOW {
+                continue
+            }
+            //flat tops count once, at their first bin
+            if before.iter().any(|r| *r >= radius) || after.iter().any(|r| *r > radius) {
+                continue
+            }
+            let lowest = before.iter().chain(after.iter()).fold(f64::MAX, |a, b| a.min(*b));
+            if radius - lowest < PROMINENCE {
+                continue
+            }
+
+            let angle = angle_of(pos);
+            let target = NOTCH_TARGETS.iter()
+                .filter(|target| angle_between(angle_of(target.pos), angle) <= TARGET_ANGLE)
+                .min_by(|a, b| angle_between(angle_of(a.pos), angle).partial_cmp(&angle_between(angle_of(b.pos), angle)).unwrap());
+            notches.push(Notch { angle, pos, target });
+        }
+        notches
+    }
+}
+
+impl Default for GateMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
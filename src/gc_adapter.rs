@@ -1,69 +1,293 @@
-use std::io::Write;
+use std::convert::TryInto;
 use std::time::{Duration, Instant};
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, SendError, Sender};
+use std::thread;
 
 use std::ffi::CString;
 
 use nix::libc;
 use rusb::{Device, DeviceHandle, GlobalContext};
 
-use crate::controller::{Controller, update_controllers};
+use crate::usbmon_text::snoop_usb_text;
 
 pub struct ControllerPoll {
+    pub adapter: usize,
     pub buffer: [u8; 37],
     pub time: Instant,
 }
 
-pub fn start_adapter_polling(sender: Sender<ControllerPoll>) {
-    for device in rusb::devices().unwrap().iter() {
-        let device_desc = device.device_descriptor().unwrap();
-        println!("Bus {:03} Device {:03} ID {:04x}:{:04x}",
-            device.bus_number(),
-            device.address(),
-            device_desc.vendor_id(),
-            device_desc.product_id());
-
-        if is_gc_adapter(&device) {
-            println!("found gc adapter");
-            let device = match start_gc_adapter(device, &sender) {
-                Err(err) => {
-                    println!("error: {:?}", err);
-                    println!("source: {:?}", err.source());
-                    println!("description: {:?}", err.to_string());
-                    break;
-                },
-                Ok(device) => {
-                    device
-                },
-            };
-            match try_snoop_usb(device, &sender) {
-                Ok(_) => println!("returned ok?"),
-                Err(err) => {
-                    println!("error in usb snoop {:?}, description {}", err, err.to_string());
-                }
+/// Which ports' rumble motors should be on.
+pub type RumbleState = [bool; 4];
+
+/// The adapter's rumble command, 0x11 followed by one motor state per port.
+pub fn rumble_command(motors: RumbleState) -> [u8; 5] {
+    let mut command = [0x11, 0, 0, 0, 0];
+    for (i, on) in motors.iter().enumerate() {
+        command[i + 1] = *on as u8;
+    }
+    command
+}
+
+/// Rumble state for one adapter, by adapter id.
+pub type RumbleRequest = (usize, RumbleState);
+
+/// Everything but `Poll` carries the adapter id.
+pub enum AdapterEvent {
+    Poll(ControllerPoll),
+    Connected(usize),
+    Disconnected(usize),
+}
+
+/// What an adapter's thread sends its polls through. `Connected` only goes out
+/// with the first poll, so an adapter we can't read never shows up as connected.
+pub struct AdapterSender {
+    sender: Sender<AdapterEvent>,
+    pub adapter: usize,
+    connected: bool,
+}
+
+impl AdapterSender {
+    pub fn send_poll(&mut self, buffer: [u8; 37], time: Instant) -> Result<(), SendError<AdapterEvent>> {
+        if !self.connected {
+            self.sender.send(AdapterEvent::Connected(self.adapter))?;
+            self.connected = true;
+        }
+        self.sender.send(AdapterEvent::Poll(ControllerPoll { adapter: self.adapter, buffer, time }))
+    }
+}
+
+/// Something that produces `ControllerPoll`s, picked at startup so the viewer
+/// can run without an adapter plugged in.
+pub trait InputSource: Send {
+    fn name(&self) -> &'static str;
+    fn run(&mut self, sender: &Sender<AdapterEvent>) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Sources that can't rumble just drop the receiver.
+    fn set_rumble_receiver(&mut self, _rumble: Receiver<RumbleRequest>) {}
+}
+
+pub fn start_input_source(mut source: Box<dyn InputSource>, sender: Sender<AdapterEvent>) {
+    eprintln!("reading inputs from {}", source.name());
+    if let Err(err) = source.run(&sender) {
+        eprintln!("error in input source {}: {:?}, description {}", source.name(), err, err);
+    }
+}
+
+/// Reads the adapter with libusb, falling back to snooping usbmon when
+/// something else (dolphin) already has the adapter claimed.
+pub struct LibusbSource {
+    pub usbmon_fallback: bool,
+    pub rumble: Option<Receiver<RumbleRequest>>,
+}
+
+impl InputSource for LibusbSource {
+    fn name(&self) -> &'static str {
+        "libusb"
+    }
+
+    fn run(&mut self, sender: &Sender<AdapterEvent>) -> Result<(), Box<dyn std::error::Error>> {
+        list_usb_devices();
+        let usbmon_fallback = self.usbmon_fallback;
+        watch_for_adapters(sender, self.rumble.as_ref(), move |device, sender, rumble| {
+            let device = start_gc_adapter(device, sender, Some(&rumble))?;
+            if !usbmon_fallback {
+                Err("gc adapter is busy, is dolphin running?")?;
+            }
+            snoop_usb(device, sender)
+        })
+    }
+
+    fn set_rumble_receiver(&mut self, rumble: Receiver<RumbleRequest>) {
+        self.rumble = Some(rumble);
+    }
+}
+
+/// Only snoops usbmon, never claims the adapter.
+pub struct UsbmonSource {
+}
+
+impl InputSource for UsbmonSource {
+    fn name(&self) -> &'static str {
+        "usbmon"
+    }
+
+    fn run(&mut self, sender: &Sender<AdapterEvent>) -> Result<(), Box<dyn std::error::Error>> {
+        list_usb_devices();
+        watch_for_adapters(sender, None, |device, sender, _rumble| snoop_usb(device, sender))
+    }
+}
+
+/// Generates fake inputs at 125hz, spins both sticks and taps A once a second.
+pub struct SyntheticSource {
+}
+
+impl InputSource for SyntheticSource {
+    fn name(&self) -> &'static str {
+        "synthetic"
+    }
+
+    fn run(&mut self, sender: &Sender<AdapterEvent>) -> Result<(), Box<dyn std::error::Error>> {
+        sender.send(AdapterEvent::Connected(0))?;
+        let start = Instant::now();
+        let mut next_poll = start;
+        loop {
+            let now = Instant::now();
+            if next_poll > now {
+                thread::sleep(next_poll - now);
+            }
+            let t = (next_poll - start).as_secs_f64();
+
+            let mut buffer = [0u8; 37];
+            buffer[0] = 0x21;
+            buffer[1] = 0x10;
+            //start at the center so the controller picks it up as the origin
+            let radius = if t < 0.5 { 0. } else { 100. };
+            let angle = t * std::f64::consts::PI;
+            if t % 1. < 0.1 {
+                buffer[2] |= 0x01;//A
+            }
+            buffer[4] = (128. + radius * angle.cos()) as u8;
+            buffer[5] = (128. + radius * angle.sin()) as u8;
+            buffer[6] = (128. - radius * angle.sin()) as u8;
+            buffer[7] = (128. + radius * angle.cos()) as u8;
+            buffer[8] = 0x20;
+            buffer[9] = 0x20;
+
+            sender.send(AdapterEvent::Poll(ControllerPoll { adapter: 0, buffer, time: next_poll }))?;
+            next_poll += Duration::from_millis(8);
+        }
+    }
+}
+
+pub fn list_usb_devices() {
+    if let Ok(devices) = rusb::devices() {
+        for device in devices.iter() {
+            if let Ok(device_desc) = device.device_descriptor() {
+                eprintln!("Bus {:03} Device {:03} ID {:04x}:{:04x}",
+                    device.bus_number(),
+                    device.address(),
+                    device_desc.vendor_id(),
+                    device_desc.product_id());
+            }
+        }
+    }
+}
+
+pub fn find_gc_adapters() -> Vec<Device<GlobalContext>> {
+    match rusb::devices() {
+        Ok(devices) => devices.iter().filter(is_gc_adapter).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn is_still_connected(device: &Device<GlobalContext>) -> bool {
+    match rusb::devices() {
+        Ok(devices) => devices.iter().any(|other| {
+            is_gc_adapter(&other) && other.bus_number() == device.bus_number() && other.address() == device.address()
+        }),
+        Err(_) => false,
+    }
+}
+
+struct RunningAdapter {
+    id: usize,
+    bus_number: u8,
+    address: u8,
+    rumble: Sender<RumbleState>,
+    thread: thread::JoinHandle<()>,
+}
+
+/// Keeps looking for adapters, and runs `connected` on its own thread for
+/// every adapter until it's unplugged (or errors out). Adapters get the
+/// lowest id that isn't in use, so replugging the only adapter keeps id 0.
+/// One that errors out is left alone until it's unplugged, rather than
+/// failing the same way every second.
+fn watch_for_adapters<F>(sender: &Sender<AdapterEvent>, rumble: Option<&Receiver<RumbleRequest>>, connected: F) -> Result<(), Box<dyn std::error::Error>>
+where F: Fn(Device<GlobalContext>, &mut AdapterSender, Receiver<RumbleState>) -> Result<(), Box<dyn std::error::Error>> + Send + Clone + 'static
+{
+    let mut adapters: Vec<RunningAdapter> = Vec::new();
+    //bus number and address of adapters whose thread stopped
+    let mut stopped: Vec<(u8, u8)> = Vec::new();
+    let mut printed_waiting = false;
+    loop {
+        adapters.retain(|adapter| {
+            let finished = adapter.thread.is_finished();
+            if finished {
+                stopped.push((adapter.bus_number, adapter.address));
             }
+            !finished
+        });
+        let devices = find_gc_adapters();
+        stopped.retain(|(bus_number, address)| devices.iter().any(|device| device.bus_number() == *bus_number && device.address() == *address));
+        for device in devices {
+            let location = (device.bus_number(), device.address());
+            if stopped.contains(&location) || adapters.iter().any(|adapter| (adapter.bus_number, adapter.address) == location) {
+                continue
+            }
+            let id = (0..).find(|id| !adapters.iter().any(|adapter| adapter.id == *id)).expect("ran out of ids");
+            eprintln!("found gc adapter {} on bus {:03} device {:03}", id, device.bus_number(), device.address());
+            printed_waiting = false;
 
-            break;
+            let (rumble_sender, rumble_receiver) = channel();
+            let (bus_number, address) = (device.bus_number(), device.address());
+            let sender = sender.clone();
+            let connected = connected.clone();
+            let thread = thread::spawn(move || {
+                let mut sender = AdapterSender { sender, adapter: id, connected: false };
+                match connected(device, &mut sender, rumble_receiver) {
+                    Err(err) if sender.connected => eprintln!("lost gc adapter {}: {}", id, err),
+                    Err(err) => eprintln!("couldn't read gc adapter {}, replug it to try again: {}", id, err),
+                    Ok(()) => {}
+                }
+                if sender.connected {
+                    let _ = sender.sender.send(AdapterEvent::Disconnected(id));
+                }
+            });
+            adapters.push(RunningAdapter { id, bus_number, address, rumble: rumble_sender, thread });
+        }
+        if adapters.is_empty() && !printed_waiting {
+            eprintln!("waiting for gc adapter");
+            printed_waiting = true;
+        }
+
+        //wait a bit before looking again, passing rumble along to the right adapter meanwhile
+        let next_scan = Instant::now() + Duration::from_secs(1);
+        match rumble {
+            Some(rumble) => loop {
+                match rumble.recv_timeout(next_scan.saturating_duration_since(Instant::now())) {
+                    Ok((id, state)) => {
+                        if let Some(adapter) = adapters.iter().find(|adapter| adapter.id == id) {
+                            let _ = adapter.rumble.send(state);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        thread::sleep(next_scan.saturating_duration_since(Instant::now()));
+                        break
+                    }
+                }
+            },
+            None => thread::sleep(Duration::from_secs(1)),
         }
     }
 }
 
 #[repr(C)]
 #[derive(Default, Debug)]
-struct UsbmonPacket {
-    id: u64,			/*  0: URB ID - from submission to callback */
-	type_: libc::c_char,	/*  8: Same as text; extensible. */
-	xfer_type: u8, /*    ISO (0), Intr, Control, Bulk (3) */
-	epnum: u8,	/*     Endpoint number and transfer direction */
-	devnum: u8,	/*     Device address */
-	busnum: u16,		/* 12: Bus number */
-	flag_setup: i8,	/* 14: Same as text */
-	flag_data: i8,		/* 15: Same as text; Binary zero is OK. */
-	ts_sec: u64,		/* 16: gettimeofday */
-	ts_usec: u32,		/* 24: gettimeofday */
-	status: i32,		/* 28: */
-	length: u32,	/* 32: Length of data (submitted or actual) */
-	len_cap: u32,	/* 36: Delivered length */
+pub struct UsbmonPacket {
+    pub id: u64,			/*  0: URB ID - from submission to callback */
+	pub type_: libc::c_char,	/*  8: Same as text; extensible. */
+	pub xfer_type: u8, /*    ISO (0), Intr, Control, Bulk (3) */
+	pub epnum: u8,	/*     Endpoint number and transfer direction */
+	pub devnum: u8,	/*     Device address */
+	pub busnum: u16,		/* 12: Bus number */
+	pub flag_setup: i8,	/* 14: Same as text */
+	pub flag_data: i8,		/* 15: Same as text; Binary zero is OK. */
+	pub ts_sec: u64,		/* 16: gettimeofday */
+	pub ts_usec: u32,		/* 24: gettimeofday */
+	pub status: i32,		/* 28: */
+	pub length: u32,	/* 32: Length of data (submitted or actual) */
+	pub len_cap: u32,	/* 36: Delivered length */
     /*
 	union {			/* 40: */
 		unsigned char setup[SETUP_LEN],	/* Only for Control S-type */
@@ -73,13 +297,46 @@ struct UsbmonPacket {
 		} iso;
 	} s;
     */
-    s: u64,
-	interval: i32,		/* 48: Only for Interrupt and ISO */
-	start_frame: i32,	/* 52: For ISO */
-	xfer_flags: u32, /* 56: copy of URB's transfer_flags */
-	ndesc: u32,	/* 60: Actual number of ISO descriptors */
+    pub s: u64,
+	pub interval: i32,		/* 48: Only for Interrupt and ISO */
+	pub start_frame: i32,	/* 52: For ISO */
+	pub xfer_flags: u32, /* 56: copy of URB's transfer_flags */
+	pub ndesc: u32,	/* 60: Actual number of ISO descriptors */
 }				/* 64 total length */ 
 
+impl UsbmonPacket {
+    /// Reads a header out of a capture file, which uses the same layout.
+    /// Older captures only have the first 48 bytes, the rest is left zeroed.
+    pub fn from_bytes(bytes: &[u8]) -> Option<UsbmonPacket> {
+        let u32_at = |i: usize| Some(u32::from_le_bytes(bytes.get(i..i + 4)?.try_into().ok()?));
+        let u64_at = |i: usize| Some(u64::from_le_bytes(bytes.get(i..i + 8)?.try_into().ok()?));
+        let mut packet = UsbmonPacket {
+            id: u64_at(0)?,
+            type_: *bytes.get(8)? as libc::c_char,
+            xfer_type: *bytes.get(9)?,
+            epnum: *bytes.get(10)?,
+            devnum: *bytes.get(11)?,
+            busnum: u16::from_le_bytes(bytes.get(12..14)?.try_into().ok()?),
+            flag_setup: *bytes.get(14)? as i8,
+            flag_data: *bytes.get(15)? as i8,
+            ts_sec: u64_at(16)?,
+            ts_usec: u32_at(24)?,
+            status: u32_at(28)? as i32,
+            length: u32_at(32)?,
+            len_cap: u32_at(36)?,
+            s: u64_at(40)?,
+            ..Default::default()
+        };
+        if bytes.len() >= 64 {
+            packet.interval = u32_at(48)? as i32;
+            packet.start_frame = u32_at(52)? as i32;
+            packet.xfer_flags = u32_at(56)?;
+            packet.ndesc = u32_at(60)?;
+        }
+        Some(packet)
+    }
+}
+
 #[repr(C)]
 pub struct MonGetArg {
     hdr: *mut UsbmonPacket,
@@ -90,15 +347,44 @@ pub struct MonGetArg {
     //size_t alloc;       /* Length of data (can be zero) */
 }
 
+/// Uses the binary usbmon interface if we can, otherwise the text one.
+fn snoop_usb(device: Device<GlobalContext>, sender: &mut AdapterSender) -> Result<(), Box<dyn std::error::Error>> {
+    if std::fs::File::open("/dev/usbmon0").is_ok() {
+        try_snoop_usb(device, sender)
+    }
+    else {
+        eprintln!("can't open /dev/usbmon0, falling back to text usbmon");
+        snoop_usb_text(device, sender)
+    }
+}
+
+/// Only reads the text usbmon interface.
+pub struct UsbmonTextSource {
+}
+
+impl InputSource for UsbmonTextSource {
+    fn name(&self) -> &'static str {
+        "usbmon text"
+    }
+
+    fn run(&mut self, sender: &Sender<AdapterEvent>) -> Result<(), Box<dyn std::error::Error>> {
+        list_usb_devices();
+        watch_for_adapters(sender, None, |device, sender, _rumble| snoop_usb_text(device, sender))
+    }
+}
+
 //cursed c shit
-fn try_snoop_usb(device: Device<GlobalContext>, sender: &Sender<ControllerPoll>) -> Result<(), Box<dyn std::error::Error>> {
+fn try_snoop_usb(device: Device<GlobalContext>, sender: &mut AdapterSender) -> Result<(), Box<dyn std::error::Error>> {
     unsafe {
         let path = CString::new("/dev/usbmon0")?;
         let usbmon_file = nix::libc::open(path.as_ptr(), 0);
-        println!("opened usbmon file, fd {} errno {}", usbmon_file, nix::errno::errno());
+        eprintln!("opened usbmon file, fd {} errno {}", usbmon_file, nix::errno::errno());
         if usbmon_file == -1 && nix::errno::errno() == nix::libc::EACCES {
             Err("usb sniffing failed, probably because we're not root")?;
         }
+        else if usbmon_file == -1 {
+            Err("usb sniffing failed, is the usbmon module loaded?")?;
+        }
 
         const MON_IOC_MAGIC: u64 = 0x92;
         //https://www.kernel.org/doc/Documentation/usb/usbmon.txt
@@ -111,37 +397,48 @@ fn try_snoop_usb(device: Device<GlobalContext>, sender: &Sender<ControllerPoll>)
             alloc: data.len(),
         };
         let mut unix_epoch = None;
-        loop {
+        let mut last_connected_check = Instant::now();
+        let result = loop {
             let res = mon_iocx_getx(usbmon_file, &mut event);
             if let None = unix_epoch {
                 unix_epoch = Some(Instant::now() - Duration::from_secs(packet_info.ts_sec) - Duration::from_micros(packet_info.ts_usec as u64));
-                println!("first data {:?}", data);
-                println!("cfg {:?}", packet_info);
+                eprintln!("first data {:?}", data);
+                eprintln!("cfg {:?}", packet_info);
             }
             //I think this filters everything?
             if packet_info.devnum == device.address() && packet_info.busnum == device.bus_number() as u16 && packet_info.type_ == 'C' as i8 {
-                //println!("got data {:?}", data);
+                //eprintln!("got data {:?}", data);
                 if data[4] == 0 || data[5] == 0 || data[6] == 0 || data[7] == 0 {
-                    println!("wrong data? {:?}", data);
-                    println!("cfg {:?}", packet_info);
+                    eprintln!("wrong data? {:?}", data);
+                    eprintln!("cfg {:?}", packet_info);
                 }
 
-                sender.send(ControllerPoll {
-                    buffer: data,
-                    time: unix_epoch.unwrap() + Duration::from_secs(packet_info.ts_sec) + Duration::from_micros(packet_info.ts_usec as u64)
-                })?;
+                let time = unix_epoch.unwrap() + Duration::from_secs(packet_info.ts_sec) + Duration::from_micros(packet_info.ts_usec as u64);
+                if let Err(err) = sender.send_poll(data, time) {
+                    break Err(err.into());
+                }
                 if let Err(e) = res {
-                    println!("error {:?}", e);
+                    eprintln!("error {:?}", e);
                 }
             }
-        }
+
+            //usbmon doesn't tell us when the adapter goes away, so check every so often
+            if last_connected_check.elapsed() > Duration::from_secs(1) {
+                if !is_still_connected(&device) {
+                    break Err("gc adapter unplugged".into());
+                }
+                last_connected_check = Instant::now();
+            }
+        };
+        nix::libc::close(usbmon_file);
+        result
     }
 }
 
-fn start_gc_adapter(device: Device<GlobalContext>, sender: &Sender<ControllerPoll>) -> Result<Device<GlobalContext>, Box<dyn std::error::Error>> {
-    println!("device speed {:?}", device.speed());
+fn start_gc_adapter(device: Device<GlobalContext>, sender: &mut AdapterSender, rumble: Option<&Receiver<RumbleState>>) -> Result<Device<GlobalContext>, Box<dyn std::error::Error>> {
+    eprintln!("device speed {:?}", device.speed());
     let mut handle = device.open()?;
-    println!("{:?}", handle);
+    eprintln!("{:?}", handle);
     let config = device.config_descriptor(0)?;
     let mut endpoint_in = 0;
     let mut endpoint_out = 0;
@@ -149,7 +446,7 @@ fn start_gc_adapter(device: Device<GlobalContext>, sender: &Sender<ControllerPol
     for interface in config.interfaces() {
         for descriptor in interface.descriptors() {
             for endpoint_descriptor in descriptor.endpoint_descriptors() {
-                println!("endpoint found {:?}", endpoint_descriptor);
+                eprintln!("endpoint found {:?}", endpoint_descriptor);
                 if endpoint_descriptor.address() & rusb::constants::LIBUSB_ENDPOINT_IN != 0 {
                     endpoint_in = endpoint_descriptor.address();
                     interface_to_claim = interface.number();
@@ -171,7 +468,10 @@ fn start_gc_adapter(device: Device<GlobalContext>, sender: &Sender<ControllerPol
         }
     }
     handle.write_interrupt(endpoint_out, &[0x13], Duration::from_millis(32))?;
-    poll_loop(handle, endpoint_in, sender)
+    let result = poll_loop(&handle, endpoint_in, endpoint_out, sender, rumble);
+    //don't leave the motors buzzing after we stop
+    let _ = handle.write_interrupt(endpoint_out, &rumble_command([false; 4]), Duration::from_millis(32));
+    result
 }
 
 pub fn is_gc_adapter(device: &Device<GlobalContext>) -> bool {
@@ -183,40 +483,56 @@ pub fn is_gc_adapter(device: &Device<GlobalContext>) -> bool {
     }
 }
 
-pub fn poll_loop(handle: DeviceHandle<GlobalContext>, endpoint_in: u8, sender: &Sender<ControllerPoll>) -> Result<Device<GlobalContext>, Box<dyn std::error::Error>> {
-    let mut controllers = [Controller::new(); 4];
-    let mut time = Instant::now();
-    let mut time_diff = 0;
-    let mut last_print = Instant::now();
+pub fn poll_loop(handle: &DeviceHandle<GlobalContext>, endpoint_in: u8, endpoint_out: u8, sender: &mut AdapterSender, rumble: Option<&Receiver<RumbleState>>) -> Result<Device<GlobalContext>, Box<dyn std::error::Error>> {
+    //what the motors were last set to, and what they should be
+    let mut rumble_state = [false; 4];
+    let mut rumble_wanted = [false; 4];
     let mut err_count = 0;
     loop {
+        if let Some(rumble) = rumble {
+            rumble_wanted = rumble.try_iter().last().unwrap_or(rumble_wanted);
+            if rumble_wanted != rumble_state {
+                //a failed write gets tried again next time round
+                match handle.write_interrupt(endpoint_out, &rumble_command(rumble_wanted), Duration::from_millis(32)) {
+                    Ok(_) => rumble_state = rumble_wanted,
+                    Err(res) => {
+                        if err_count >= 10 || res == rusb::Error::NoDevice {
+                            return Err(Box::new(res));
+                        }
+                        err_count += 1;
+                        eprintln!("error writing rumble: {:?}", res);
+                    }
+                }
+            }
+        }
+
         let mut buffer = [0u8; 37];
         let res = handle.read_interrupt(endpoint_in, &mut buffer, Duration::from_millis(32));
         let now = Instant::now();
         if let Err(res) = res {
-            if err_count >= 10 {
+            if err_count >= 10 || res == rusb::Error::NoDevice {
                 return Err(Box::new(res));
             }
             err_count += 1;
-            println!("error reading: {:?}", res);
+            eprintln!("error reading: {:?}", res);
         }
         else {
             err_count = 0;
-            sender.send(ControllerPoll { buffer, time: now })?;
+            sender.send_poll(buffer, now)?;
         }
-        let new_time = Instant::now();
-        if new_time - last_print > Duration::from_millis(100) {
-            update_controllers(&mut controllers, &buffer);
-            print!("\rbtns: [{:<20}] stk: [{:<4?}] c: [{:<4?}]", controllers[0].to_string(), controllers[0].stick_clamp(), controllers[0].c_stick_clamp());
-            print!("p2: [{:<20}] stk: [{:<4?}] c: [{:<4?}]", controllers[1].to_string(), controllers[1].stick_clamp(), controllers[1].c_stick_clamp());
-            print!("time: {:>4}", time_diff);
-            std::io::stdout().flush()?;
-            last_print = new_time;
-        }
-
-        //sleep(Duration::from_micros(1000));
-        time_diff = (new_time - time).as_micros();
-        time = new_time;
     }
     //Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rumble_command_bytes() {
+        assert_eq!(rumble_command([false; 4]), [0x11, 0, 0, 0, 0]);
+        assert_eq!(rumble_command([true; 4]), [0x11, 1, 1, 1, 1]);
+        assert_eq!(rumble_command([true, false, false, true]), [0x11, 1, 0, 0, 1]);
+        assert_eq!(rumble_command([false, true, false, false]), [0x11, 0, 1, 0, 0]);
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::mpsc::Receiver;
+
+use gcviewer::analysis::{CompletedSequence, SequenceTracker};
+use gcviewer::calibration::ProfileStore;
+use gcviewer::controller::{self, Controller, update_controllers};
+use gcviewer::duration_to_frame_count;
+use gcviewer::gc_adapter::AdapterEvent;
+use gcviewer::session::SessionWriter;
+
+#[derive(Clone, Copy)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// Does what the window does with polls, minus the drawing, and prints
+/// every completed sequence. Returns once the input source finishes.
+pub fn run(receiver: Receiver<AdapterEvent>, mut tracker: SequenceTracker, profiles: ProfileStore, mut session_writer: Option<SessionWriter>, format: OutputFormat) {
+    let mut controllers: Vec<Controller> = (0..4).map(|index| profiles.new_controller(index)).collect();
+    let mut current_controller = 0;
+    let mut start = None;
+
+    for event in receiver {
+        let poll = match event {
+            AdapterEvent::Poll(poll) => poll,
+            AdapterEvent::Connected(adapter) => {
+                print_event(format, "connected", adapter);
+                continue
+            }
+            AdapterEvent::Disconnected(adapter) => {
+                print_event(format, "disconnected", adapter);
+                for (index, controller) in controllers.iter_mut().enumerate().skip(adapter * 4).take(4) {
+                    *controller = profiles.new_controller(index);
+                }
+                continue
+            }
+        };
+        if let Some(writer) = session_writer.as_mut() {
+            if let Err(err) = writer.write_poll(&poll) {
+                eprintln!("couldn't write to session file, stopping recording: {}", err);
+                session_writer = None;
+            }
+        }
+        let start = *start.get_or_insert(poll.time);
+        for index in controllers.len()..poll.adapter * 4 + 4 {
+            controllers.push(profiles.new_controller(index));
+        }
+        update_controllers(&mut controllers[poll.adapter * 4..poll.adapter * 4 + 4], &poll.buffer);
+
+        //push the A button on a controller to switch to it, same as the window
+        for (i, controller) in controllers.iter().enumerate() {
+            if controller.is_down(&controller::A_BUTTON) && i != current_controller {
+                current_controller = i;
+                if let OutputFormat::Text = format {
+                    println!("switched to adapter {} port {}", i / 4, i % 4 + 1);
+                }
+            }
+        }
+
+        for sequence in tracker.update(&controllers[current_controller], poll.time) {
+            match format {
+                OutputFormat::Text => print_text(&sequence, current_controller),
+                OutputFormat::Json => println!("{}", sequence_json(&sequence, current_controller, poll.time.saturating_duration_since(start).as_secs_f64())),
+            }
+        }
+    }
+    if let Some(writer) = session_writer.as_mut() {
+        if let Err(err) = writer.flush() {
+            eprintln!("couldn't write to session file: {}", err);
+        }
+    }
+    if let OutputFormat::Text = format {
+        println!("input source finished");
+    }
+}
+
+fn print_event(format: OutputFormat, event: &str, adapter: usize) {
+    match format {
+        OutputFormat::Text => println!("adapter {} {}", adapter, event),
+        OutputFormat::Json => println!("{{\"event\":\"{}\",\"adapter\":{}}}", event, adapter),
+    }
+}
+
+fn print_text(sequence: &CompletedSequence, port: usize) {
+    let (name, actions, success_rate) = sequence;
+    println!("adapter {} port {}: {}, chance of success: {}%", port / 4, port % 4 + 1, name, success_rate * 100.);
+    for (input, since_last, success) in actions {
+        println!("    {}, time: {:.3} frames ({} ms) {}", input.to_string(), duration_to_frame_count(*since_last), since_last.as_millis(), success);
+    }
+}
+
+/// One line of json per sequence, `time` is seconds since the first poll.
+pub fn sequence_json(sequence: &CompletedSequence, port: usize, time: f64) -> String {
+    let (name, actions, success_rate) = sequence;
+    let actions: Vec<String> = actions.iter().map(|(input, since_last, success)| {
+        format!("{{\"action\":{},\"frames\":{:.3},\"ms\":{:.3},\"result\":{}}}",
+            json_string(&input.to_string()),
+            duration_to_frame_count(*since_last),
+            since_last.as_secs_f64() * 1000.,
+            json_string(&success.to_string()))
+    }).collect();
+    format!("{{\"event\":\"sequence\",\"time\":{:.6},\"adapter\":{},\"port\":{},\"sequence\":{},\"success_rate\":{},\"actions\":[{}]}}",
+        time, port / 4, port % 4 + 1, json_string(name), success_rate, actions.join(","))
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => escaped += "\\\"",
+            '\\' => escaped += "\\\\",
+            c if (c as u32) < 0x20 => escaped += &format!("\\u{:04x}", c as u32),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
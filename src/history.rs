@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// The raw inputs of every controller for the last `length`, kept even while
+/// the window is paused so hovering over the scopes can look back at them.
+pub struct InputHistory {
+    pub length: Duration,
+    //one per controller, oldest first, same indices as the controllers
+    controllers: Vec<VecDeque<(Instant, [u8; 8])>>,
+}
+
+impl InputHistory {
+    pub fn new(length: Duration) -> Self {
+        Self { length, controllers: vec![] }
+    }
+
+    pub fn record(&mut self, index: usize, time: Instant, buffer: [u8; 8]) {
+        if self.controllers.len() <= index {
+            self.controllers.resize_with(index + 1, VecDeque::new);
+        }
+        let polls = &mut self.controllers[index];
+        polls.push_back((time, buffer));
+        while let Some((oldest, _)) = polls.front() {
+            if time.saturating_duration_since(*oldest) <= self.length {
+                break
+            }
+            polls.pop_front();
+        }
+    }
+
+    /// The last inputs at or before `time`, or none if that's older than the history goes.
+    pub fn state_at(&self, index: usize, time: Instant) -> Option<[u8; 8]> {
+        let polls = self.controllers.get(index)?;
+        let after = polls.partition_point(|(poll_time, _)| *poll_time <= time);
+        let (_, buffer) = polls.get(after.checked_sub(1)?)?;
+        Some(*buffer)
+    }
+
+    /// Every poll from `start` up to but not including `end`, oldest first.
+    pub fn range(&self, index: usize, start: Instant, end: Instant) -> impl Iterator<Item = &(Instant, [u8; 8])> + '_ {
+        self.controllers.get(index).into_iter().flat_map(move |polls| {
+            let first = polls.partition_point(|(time, _)| *time < start);
+            let last = polls.partition_point(|(time, _)| *time < end).max(first);
+            polls.range(first..last)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a poll every 10ms from start, each buffer's first byte is its number
+    fn history(start: Instant, polls: u8) -> InputHistory {
+        let mut history = InputHistory::new(Duration::from_secs(1));
+        for i in 0..polls {
+            history.record(0, start + Duration::from_millis(i as u64 * 10), [i, 0, 0, 0, 0, 0, 0, 0]);
+        }
+        history
+    }
+
+    #[test]
+    fn state_at_before_between_and_after() {
+        let start = Instant::now() + Duration::from_secs(1);
+        let history = history(start, 5);
+        assert_eq!(history.state_at(0, start - Duration::from_millis(1)), None);
+        assert_eq!(history.state_at(0, start).map(|buffer| buffer[0]), Some(0));
+        assert_eq!(history.state_at(0, start + Duration::from_millis(15)).map(|buffer| buffer[0]), Some(1));
+        assert_eq!(history.state_at(0, start + Duration::from_millis(20)).map(|buffer| buffer[0]), Some(2));
+        assert_eq!(history.state_at(0, start + Duration::from_secs(5)).map(|buffer| buffer[0]), Some(4));
+        assert_eq!(history.state_at(1, start), None);
+    }
+
+    #[test]
+    fn old_polls_drop_off() {
+        let start = Instant::now();
+        let mut history = InputHistory::new(Duration::from_millis(25));
+        for i in 0..5u8 {
+            history.record(0, start + Duration::from_millis(i as u64 * 10), [i, 0, 0, 0, 0, 0, 0, 0]);
+        }
+        assert_eq!(history.state_at(0, start + Duration::from_millis(10)), None);
+        assert_eq!(history.state_at(0, start + Duration::from_millis(20)).map(|buffer| buffer[0]), Some(2));
+    }
+
+    #[test]
+    fn range_is_start_up_to_end() {
+        let start = Instant::now();
+        let history = history(start, 5);
+        let numbers = |from: u64, to: u64| history.range(0, start + Duration::from_millis(from), start + Duration::from_millis(to))
+            .map(|(_, buffer)| buffer[0]).collect::<Vec<_>>();
+        assert_eq!(numbers(0, 40), vec![0, 1, 2, 3]);
+        assert_eq!(numbers(5, 25), vec![1, 2]);
+        assert_eq!(numbers(0, 1000), vec![0, 1, 2, 3, 4]);
+        assert_eq!(numbers(30, 10), Vec::<u8>::new());
+        assert_eq!(history.range(3, start, start + Duration::from_secs(1)).count(), 0);
+    }
+}
@@ -1,6 +1,6 @@
 use std::{fmt::Display, ops::{Range}, time::{Duration, Instant}};
 
-use crate::{controller::{A_BUTTON, B_BUTTON, Button, Controller, L_BUTTON, R_BUTTON, X_BUTTON, Y_BUTTON, Z_BUTTON}, duration_to_frame_count, zones::{self, Zone, ZoneTrait}};
+use crate::{controller::{A_BUTTON, B_BUTTON, Button, Controller, L_BUTTON, R_BUTTON, X_BUTTON, Y_BUTTON, Z_BUTTON}, duration_to_frame_count, zones::{self, TriggerZone, Zone, ZoneTrait}};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum ControllerAction {
@@ -11,10 +11,10 @@ pub enum ControllerAction {
     Leave(Zone),
     CEnter(Zone),
     CLeave(Zone),
-    LEnter((u8, u8)),
-    LLeave((u8, u8)),
-    REnter((u8, u8)),
-    RLeave((u8, u8)),
+    LEnter(TriggerZone),
+    LLeave(TriggerZone),
+    REnter(TriggerZone),
+    RLeave(TriggerZone),
 }
 
 impl ToString for ControllerAction {
@@ -27,10 +27,10 @@ impl ToString for ControllerAction {
             ControllerAction::Leave(zone) => format!("Left {}", zone.get_name()),
             ControllerAction::CEnter(zone) => format!("C Entered {}", zone.get_name()),
             ControllerAction::CLeave(zone) => format!("C Left {}", zone.get_name()),
-            ControllerAction::LEnter(zone) => format!("L Entered [{}, {}]", zone.0, zone.1),
-            ControllerAction::LLeave(zone) => format!("L Left [{}, {}]", zone.0, zone.1),
-            ControllerAction::REnter(zone) => format!("R Entered [{}, {}]", zone.0, zone.1),
-            ControllerAction::RLeave(zone) => format!("R Left [{}, {}]", zone.0, zone.1),
+            ControllerAction::LEnter(zone) => format!("L Entered {}", zone.get_name()),
+            ControllerAction::LLeave(zone) => format!("L Left {}", zone.get_name()),
+            ControllerAction::REnter(zone) => format!("R Entered {}", zone.get_name()),
+            ControllerAction::RLeave(zone) => format!("R Left {}", zone.get_name()),
         }
     }
 }
@@ -81,7 +81,8 @@ impl InputSequence {
     }
 
     pub fn add(&mut self, action: impl Into<Vec<ControllerAction>>, frame_number: impl FrameRange) {
-        self.add_from(action, frame_number, self.actions.len() - 1)
+        //the first action isn't timed from anything, that's usize::MAX
+        self.add_from(action, frame_number, self.actions.len().wrapping_sub(1))
     }
 
     pub fn add_from(&mut self, action: impl Into<Vec<ControllerAction>>, frame_number: impl FrameRange, from: usize) {
@@ -89,6 +90,11 @@ impl InputSequence {
         self.actions.push(InputSequenceAction { actions: action.into(), start: range.0, end: range.1, from, is_fail: false });
     }
 
+    /// Every action in the sequence, including each of the ones that can happen together.
+    pub fn actions(&self) -> impl Iterator<Item = &ControllerAction> {
+        self.actions.iter().flat_map(|action| action.actions.iter())
+    }
+
     /// Get a reference to the controller sequence's name.
     pub fn name(&self) -> &'static str {
         self.name
@@ -132,7 +138,6 @@ impl<'a> InputSequenceState<'a> {
             if let Some((_, last_time)) = self.history.last() {
                 let time = duration_to_frame_count(now - *last_time);
                 let window = (expected_action.start, expected_action.end);
-                println!("{}, {:?}", time, window);
                 if window.0 as f64 - time > 5. || time - window.1 as f64 > 5. {
                     self.reset();
                 }
@@ -327,7 +332,7 @@ pub fn make_some_sequences() -> Vec<InputSequence> {
     ret.push(pivot);
 
     let mut adt = InputSequence::new("adt");
-    adt.add(vec![ControllerAction::LEnter((43, 140)), ControllerAction::REnter((43, 140))], 0);
+    adt.add(vec![ControllerAction::LEnter(zones::FULL_ANALOG), ControllerAction::REnter(zones::FULL_ANALOG)], 0);
     adt.add(vec![ControllerAction::Press(L_BUTTON), ControllerAction::Press(R_BUTTON)], 1);
     ret.push(adt);
 
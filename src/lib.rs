@@ -0,0 +1,28 @@
+//! Decoding and analysis for gamecube controller adapters, without any
+//! window toolkit. The gcviewer binary draws everything on top of this.
+
+use std::time::Duration;
+
+pub mod controller;
+pub mod calibration;
+pub mod gate;
+pub mod snapback;
+pub mod ucf;
+pub mod zones;
+pub mod plane_config;
+pub mod zone_library;
+pub mod input_sequence;
+pub mod analysis;
+pub mod transitions;
+pub mod history;
+
+pub mod gc_adapter;
+pub mod usbmon_text;
+pub mod session;
+pub mod pcap;
+pub mod poll_stats;
+
+/// Melee runs at 60 frames per second.
+pub fn duration_to_frame_count(duration: Duration) -> f64 {
+    duration.as_micros() as f64 / (1_000_000. / 60.)
+}
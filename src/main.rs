@@ -1,469 +1,180 @@
-use std::convert::TryInto;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use std::thread;
-use std::sync::mpsc::{channel, Receiver};
+use std::sync::mpsc::channel;
 
-use std::collections::{BTreeMap, HashMap, VecDeque};
+use gcviewer::{gc_adapter, input_sequence, plane_config, session, zones};
+use gcviewer::calibration::ProfileStore;
+use gcviewer::zones::{Plane, PlaneWithZones};
+use gcviewer::zone_library::{self, ZoneLibrary};
 
-use ggez::input::mouse;
-use ggez::{Context, ContextBuilder, GameResult};
-use ggez::conf::WindowMode;
-use ggez::graphics::{self, Color, DrawMode, DrawParam, Rect, Text, TextFragment};
-use ggez::event::{self, EventHandler};
+use gcviewer::gc_adapter::AdapterEvent;
+use gcviewer::gc_adapter::InputSource;
+use gcviewer::gc_adapter::RumbleRequest;
+use gcviewer::gc_adapter::start_input_source;
 
-mod controller;
-use controller::Controller;
-use controller::update_controllers;
-
-mod zones;
+use gcviewer::session::SessionWriter;
 
+//everything the window needs, without the gui feature there's only --headless
+#[cfg(feature = "gui")]
+mod window;
+#[cfg(feature = "gui")]
 mod oscilloscope;
-use input_sequence::InputSequenceState;
-use oscilloscope::Oscilloscope;
-use oscilloscope::ScopeDirection;
-
-mod gc_adapter;
-use gc_adapter::ControllerPoll;
-use gc_adapter::start_adapter_polling;
-
+#[cfg(feature = "gui")]
 mod stick_display;
-use stick_display::StickDisplay;
-use zones::Zone;
-
+#[cfg(feature = "gui")]
 mod button_display;
-
-use crate::input_sequence::{ActionSuccess, ControllerAction, InputSequence};
-use crate::oscilloscope::Scope;
-use crate::zones::ZoneTrait;
-
+#[cfg(feature = "gui")]
+mod zone_editor;
+#[cfg(feature = "gui")]
 mod button_scope;
-use button_scope::ButtonScope;
-
-mod input_sequence;
 
-const WIDTH: u16 = 1600;
-const HEIGHT: u16 = 1000;
+use gcviewer::analysis::SequenceTracker;
+mod headless;
 
-//const ORANGE: Color = Color {255, 127, 0};
-const ORANGE: Color = Color { r: 1., g: 0.5, b: 0., a: 1. };
+const PROFILE_PATH: &str = "calibration_profiles.txt";
+//how far back hovering over the scopes can look, --history changes it
+const HISTORY_SECONDS: f64 = 30.;
 
 fn main() {
-    let (mut ctx, event_loop) = ContextBuilder::new("gc viewer", "mister_person")
-        .window_mode(WindowMode::dimensions(Default::default(), WIDTH.into(), HEIGHT.into()))
-        .build()
-        .expect("aieee, could not create ggez context!");
-
-    let (sender, receiver) = channel::<ControllerPoll>();
-    thread::spawn( || start_adapter_polling(sender));
-
-    let input_sequences = Box::new(input_sequence::make_some_sequences()).leak();
-	let mut my_game = GameState::new(&mut ctx, receiver).unwrap();
-    my_game.input_sequences_states = input_sequences.iter().map(|seq| InputSequenceState::new(seq)).collect();
-
-	// Run!
-	event::run(ctx, event_loop, my_game);
-}
-
-enum StickPosFormat {
-    Integer,
-    Decimal,
-}
-
-type CompletedSequence = (&'static str, Vec<(ControllerAction, Duration, ActionSuccess)>, f64);
-
-struct GameState<'a> {
-    receiver: Receiver<ControllerPoll>,
-    controllers: [Controller; 4],
-    current_controller: usize,
-
-    prev_coords: VecDeque<((i8, i8), Instant)>,
-    c_prev_coords: VecDeque<((i8, i8), Instant)>,
-
-    prev_input_map: BTreeMap<Instant, [u8; 8]>,
-
-    paused: bool,
-
-    stick_display: StickDisplay,
-    c_stick_display: StickDisplay,
-
-    scope_y: Oscilloscope,
-    scope_x: Oscilloscope,
-    c_scope_y: Oscilloscope,
-    c_scope_x: Oscilloscope,
-
-    button_scope: ButtonScope,
-
-    stick_pos_format: StickPosFormat,
-
-    input_sequences_states: Vec<input_sequence::InputSequenceState<'a>>,
-    completed_sequences: Vec<CompletedSequence>,
-    used_zones: Vec<(&'a Zone, bool, bool)>,
-}
+    let options = match parse_args(&std::env::args().collect::<Vec<_>>()) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("usage: gcviewer [--libusb | --usbmon | --usbmon-text | --replay <session or pcap file> [--speed <multiplier>] | --synthetic] [--record <session file>] [--rumble] [--headless [--json]] [--profile <calibration profile name>] [--history <seconds>] [--planes <zone planes file>]");
+            return
+        }
+    };
 
-impl<'a> GameState<'a> {
-    pub fn get_controller(&self) -> Controller {
-        self.controllers[self.current_controller]
+    let (sender, receiver) = channel::<AdapterEvent>();
+    let mut source = options.source;
+    let mut rumble_sender = None;
+    if options.rumble {
+        let (sender, receiver) = channel::<RumbleRequest>();
+        source.set_rumble_receiver(receiver);
+        rumble_sender = Some(sender);
     }
+    thread::spawn(move || start_input_source(source, sender));
 
-    pub fn get_inputs_at_time(&self, time: Instant) -> [u8; 8] {
-        if let Some(inputs) = self.prev_input_map.range(time..).next() {
-            inputs.1.to_owned()
+    let session_writer = options.record_path.and_then(|path| match SessionWriter::create(&path) {
+        Ok(writer) => Some(writer),
+        Err(err) => {
+            eprintln!("couldn't create session file {}: {}", path, err);
+            None
         }
-        else {
-            [0; 8]
-        }
-    }
+    });
 
-    pub fn new<'b>(ctx: &'b mut Context, receiver: Receiver<ControllerPoll>) -> GameResult<GameState<'a>> {
-        let scope_y = Oscilloscope::new(ctx, 40., 0., 1000., 200., ScopeDirection::Horizontal)?;
-        let scope_x = Oscilloscope::new(ctx, 40., 200., 1000., 200., ScopeDirection::Horizontal)?;
-        let mut c_scope_y = Oscilloscope::new(ctx, 40., 400., 1000., 130., ScopeDirection::Horizontal)?;
-        let mut c_scope_x = Oscilloscope::new(ctx, 40., 530., 1000., 130., ScopeDirection::Horizontal)?;
-        c_scope_y.plane = Box::new(zones::CStick{});
-        c_scope_x.plane = Box::new(zones::CStick{});
-        let button_scope = ButtonScope::new(ctx, 40., 660., 1000., 180., ScopeDirection::Horizontal)?;
-        let stick_display = StickDisplay::new(ctx, 1200., 0., 440, 440)?;
-        let mut c_stick_display = StickDisplay::new(ctx, 1310., 400., 220, 220)?;
-        c_stick_display.set_plane(Box::new(zones::CStick {}));
-        let used_zones = zones::get_some_zones().iter().map(|z| (*z, false, false)).collect();
-        Ok(GameState {
-            receiver,
-            controllers: [Controller::new(); 4],
-            current_controller: 0,
-            prev_coords: VecDeque::new(),
-            c_prev_coords: VecDeque::new(),
-            paused: false,
-            scope_y,
-            scope_x,
-            c_scope_y,
-            c_scope_x,
-            stick_display,
-            c_stick_display,
-            stick_pos_format: StickPosFormat::Integer,
-            button_scope,
-            prev_input_map: BTreeMap::new(),
-            input_sequences_states: vec![],
-            used_zones,
-            completed_sequences: vec![],
-        })
+    let mut profiles = match ProfileStore::load(PROFILE_PATH) {
+        Ok(profiles) => profiles,
+        Err(err) => {
+            eprintln!("couldn't load calibration profiles from {}: {}", PROFILE_PATH, err);
+            return
+        }
+    };
+    profiles.name = options.profile;
+
+    //the file's planes, or the generic ones, then every character's
+    let mut libraries = vec![match &options.planes_path {
+        Some(path) => match load_or_create_library(path) {
+            Ok(library) => library,
+            Err(err) => {
+                eprintln!("couldn't load zone planes from {}: {}", path, err);
+                return
+            }
+        },
+        None => ZoneLibrary::new("generic", built_in_planes()),
+    }];
+    libraries.extend(zone_library::character_libraries());
+
+    //the zones the window starts with, P and L change them there
+    let mut tracker = SequenceTracker::new(Box::new(input_sequence::make_some_sequences()).leak());
+    let stick_zones = libraries[0].planes.first().map_or_else(Vec::new, |plane| plane.zones());
+    tracker.set_zones(stick_zones, libraries[0].c_stick.zones(), zones::get_some_trigger_zones());
+
+    match options.headless {
+        Some(format) => headless::run(receiver, tracker, profiles, session_writer, format),
+        #[cfg(feature = "gui")]
+        None => window::run(receiver, profiles, tracker, rumble_sender, session_writer, options.history_length, libraries),
+        //parse_args wants --headless without the window, and these are only for the window
+        #[cfg(not(feature = "gui"))]
+        None => drop((rumble_sender, options.history_length)),
     }
 }
 
-impl<'a> EventHandler<ggez::GameError> for GameState<'a> {
-    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        loop {
-            let poll = match self.receiver.try_recv() {
-                Ok(poll) => poll,
-                Err(std::sync::mpsc::TryRecvError::Empty) => break,
-                Err(std::sync::mpsc::TryRecvError::Disconnected) => panic!("couldn't read from adapter"),
-            };
-            update_controllers(&mut self.controllers, &poll.buffer);
-
-            if !self.paused {
-                self.prev_input_map.insert(poll.time, self.get_controller().buffer);
-                if self.prev_input_map.len() > 5000 {//TODO magic number
-                    let (first, _) = self.prev_input_map.iter().next().unwrap();
-                    let first = first.clone();
-                    self.prev_input_map.remove(&first);
-                }
-            }
-
-            //push the A button on a controller to switch to it
-            for (i, controller) in self.controllers.iter().enumerate() {
-                if controller.is_down(&controller::A_BUTTON) && i != self.current_controller {
-                    self.current_controller = i;
-                }
-            }
-
-            if self.get_controller().just_pressed(&controller::A_BUTTON) {
-                //self.update_background(ctx)?;
-            }
-            if self.get_controller().just_pressed(&controller::D_RIGHT_BUTTON) {
-                self.stick_pos_format = match self.stick_pos_format {
-                    StickPosFormat::Integer => StickPosFormat::Decimal,
-                    StickPosFormat::Decimal => StickPosFormat::Integer,
-                }
-            }
-            //pause if start is pressed
-            if self.paused {
-                if self.get_controller().just_pressed(&controller::START_BUTTON) {
-                    self.paused = false;
-                    self.scope_y.reset(ctx);
-                    self.scope_x.reset(ctx);
-                    self.c_scope_y.reset(ctx);
-                    self.c_scope_x.reset(ctx);
-                }
-                continue
-            }
-            else if self.get_controller().just_pressed(&controller::START_BUTTON) {
-                self.paused = true;
-            }
-
-            let buttons = self.get_controller().buffer[0..2].try_into();
-            if let Ok(buttons) = buttons {
-                self.button_scope.update(ctx, buttons, poll.time)?;
-            }
-
-            let stick_pos = self.get_controller().stick_pos();
-            let clamp_pos = controller::clamp(stick_pos.0, stick_pos.1);
-            let c_stick_pos = self.get_controller().c_stick_pos();
-            let c_clamp_pos = controller::clamp(c_stick_pos.0, c_stick_pos.1);
-
-            let controller = self.get_controller();
-
-            let mut actions = vec![];
-            actions.extend(
-                controller.buttons_just_pressed().iter().map(|button| ControllerAction::Press(**button))
-            );
-            actions.extend(
-                controller.buttons_just_released().iter().map(|button| ControllerAction::Release(**button))
-            );
-            for (i, (zone, in_last, c_in_last)) in self.used_zones.clone().iter().enumerate() {
-                let in_zone = zone.in_zone(clamp_pos);
-                if in_zone && !in_last {
-                    actions.push(ControllerAction::Enter((**zone).clone()));
-                }
-                if !in_zone && *in_last {
-                    actions.push(ControllerAction::Leave((**zone).clone()));
-                }
-                let c_in_zone = zone.in_zone(c_clamp_pos);
-                if c_in_zone && !c_in_last {
-                    actions.push(ControllerAction::CEnter((**zone).clone()));
-                }
-                if !c_in_zone && *c_in_last {
-                    actions.push(ControllerAction::CLeave((**zone).clone()));
-                }
-                self.used_zones[i].1 = in_zone;
-                self.used_zones[i].2 = c_in_zone;
-            }
-
-            //TODO generalize
-            if controller.l_analog() >= 43 && controller.l_analog_last() < 43 {
-                actions.push(ControllerAction::LEnter((43, 140)));
-            }
-            if controller.l_analog() < 43 && controller.l_analog_last() >= 43 {
-                actions.push(ControllerAction::LLeave((43, 140)));
-            }
-            if controller.r_analog() >= 43 && controller.r_analog_last() < 43 {
-                actions.push(ControllerAction::REnter((43, 140)));
-            }
-            if controller.r_analog() < 43 && controller.r_analog_last() >= 43 {
-                actions.push(ControllerAction::RLeave((43, 140)));
-            }
-
-            for seq in self.input_sequences_states.iter_mut() {
-                for action in &actions {
-                    let finished = seq.action(action.clone(), &controller, poll.time);
-                    if finished {
-                        if let Some(actions) = seq.sequence_info() {
-                            let rate = seq.success_rate().unwrap_or(0.);
-                            self.completed_sequences.push((seq.sequence.name(), actions, rate));
-                        }
-                    }
-                }
-            };
-
-            //add trail points to stick display
-            self.prev_coords.push_front((stick_pos, poll.time));
-            self.stick_display.add_point(ctx, clamp_pos)?;
-            if clamp_pos != stick_pos {
-                self.stick_display.add_point(ctx, stick_pos)?;
-            }
-
-            //TODO correlate this size with oscilloscope trail size (maybe)
-            //remove old points from stick display
-            if self.prev_coords.len() > 500 {
-                let (old_stick_pos, _time) = self.prev_coords.pop_back().expect("I just checked len, it can't be empty");
-                let old_clamp_pos = controller::clamp(old_stick_pos.0, old_stick_pos.1);
-                self.stick_display.remove_point(ctx, old_stick_pos)?;
-                if old_clamp_pos != old_stick_pos {
-                    self.stick_display.remove_point(ctx, old_clamp_pos)?;
-                }
-            }
-
-            //ditto above but c stick, TODO dry
-            self.c_prev_coords.push_front((c_stick_pos, poll.time));
-            self.c_stick_display.add_point(ctx, c_clamp_pos)?;
-            if c_clamp_pos != c_stick_pos {
-                self.c_stick_display.add_point(ctx, c_stick_pos)?;
-            }
+struct Options {
+    source: Box<dyn InputSource>,
+    record_path: Option<String>,
+    rumble: bool,
+    headless: Option<headless::OutputFormat>,
+    profile: Option<String>,
+    history_length: Duration,
+    planes_path: Option<String>,
+}
 
-            if self.c_prev_coords.len() > 500 {
-                let (old_c_stick_pos, _time) = self.c_prev_coords.pop_back().expect("I just checked len, it can't be empty");
-                let old_c_clamp_pos = controller::clamp(old_c_stick_pos.0, old_c_stick_pos.1);
-                self.c_stick_display.remove_point(ctx, old_c_stick_pos)?;
-                if old_c_clamp_pos != old_c_stick_pos {
-                    self.c_stick_display.remove_point(ctx, old_c_clamp_pos)?;
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut source: Box<dyn InputSource> = Box::new(gc_adapter::LibusbSource { usbmon_fallback: true, rumble: None });
+    let mut replay_path = None;
+    let mut speed: f64 = 1.;
+    let mut record_path = None;
+    let mut rumble = false;
+    let mut headless = None;
+    let mut profile = None;
+    let mut history_seconds = HISTORY_SECONDS;
+    let mut planes_path = None;
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--libusb" => source = Box::new(gc_adapter::LibusbSource { usbmon_fallback: false, rumble: None }),
+            "--usbmon" => source = Box::new(gc_adapter::UsbmonSource {}),
+            "--usbmon-text" => source = Box::new(gc_adapter::UsbmonTextSource {}),
+            "--synthetic" => source = Box::new(gc_adapter::SyntheticSource {}),
+            "--replay" => replay_path = Some(args.next().ok_or("--replay needs a session or pcap file")?.clone()),
+            "--speed" => {
+                let value = args.next().ok_or("--speed needs a multiplier")?;
+                speed = value.parse().map_err(|_| format!("bad speed {}", value))?;
+                if speed.is_nan() || speed <= 0. {
+                    return Err(format!("bad speed {}", value));
                 }
             }
-
-            self.scope_x.update(ctx, (clamp_pos.0, clamp_pos), poll.time)?;
-            self.scope_y.update(ctx, (clamp_pos.1, clamp_pos), poll.time)?;
-            self.c_scope_x.update(ctx, (c_clamp_pos.0, c_clamp_pos), poll.time)?;
-            self.c_scope_y.update(ctx, (c_clamp_pos.1, c_clamp_pos), poll.time)?;
-
-        }
-        Ok(())
-    }
-
-    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        graphics::clear(ctx, Color::BLACK);
-
-        self.stick_display.draw(ctx)?;
-
-        if self.get_controller().stick_clamp() != self.get_controller().stick_pos() {
-            self.stick_display.draw_point(ctx, self.get_controller().stick_pos(), Color::RED)?;
-        }
-        self.stick_display.draw_point(ctx, self.get_controller().stick_clamp(), Color::WHITE)?;
-
-        self.c_stick_display.draw(ctx)?;
-
-        self.button_scope.draw(ctx)?;
-
-        button_display::draw_buttons(ctx, &self.get_controller(), 1050., 660., button_display::LINE_LAYOUT)?;
-
-        self.scope_y.draw(ctx)?;
-        self.scope_x.draw(ctx)?;
-        self.c_scope_y.draw(ctx)?;
-        self.c_scope_x.draw(ctx)?;
-
-        let mouse_pos = mouse::position(ctx);
-        let mut instant = None;
-        instant = instant.or_else(|| self.scope_y.get_time_from_pos(mouse_pos.x, mouse_pos.y));
-        instant = instant.or_else(|| self.scope_x.get_time_from_pos(mouse_pos.x, mouse_pos.y));
-        instant = instant.or_else(|| self.c_scope_y.get_time_from_pos(mouse_pos.x, mouse_pos.y));
-        instant = instant.or_else(|| self.c_scope_x.get_time_from_pos(mouse_pos.x, mouse_pos.y));
-        instant = instant.or_else(|| self.button_scope.get_time_from_pos(mouse_pos.x, mouse_pos.y));
-        if let Some(instant) = instant {
-            let text = self.scope_y.scope_start_time.saturating_duration_since(instant).as_millis().to_string();
-            draw_text(ctx, text, 200., 0., Color::WHITE)?;
-            self.scope_y.draw_line_at_time(ctx, instant)?;
-            self.scope_x.draw_line_at_time(ctx, instant)?;
-            self.c_scope_y.draw_line_at_time(ctx, instant)?;
-            self.c_scope_x.draw_line_at_time(ctx, instant)?;
-            self.button_scope.draw_line_at_time(ctx, instant)?;
-
-            let mut controller = self.get_controller().clone();
-            controller.from_buffer(&self.get_inputs_at_time(instant));
-            let point = controller.stick_clamp();
-            for x in -1..=1 {
-                for y in -1..=1 {
-                    let color = if x == 0 && y == 0 {Color::BLACK} else {Color::WHITE};
-                    self.stick_display.draw_point(ctx, (point.0 + x, point.1 + y), color)?;
+            "--record" => record_path = Some(args.next().ok_or("--record needs a session file")?.clone()),
+            "--rumble" => rumble = true,
+            "--headless" => headless = Some(headless.unwrap_or(headless::OutputFormat::Text)),
+            "--json" => headless = Some(headless::OutputFormat::Json),
+            "--profile" => profile = Some(args.next().ok_or("--profile needs a name")?.clone()),
+            "--planes" => planes_path = Some(args.next().ok_or("--planes needs a zone planes file")?.clone()),
+            "--history" => {
+                let value = args.next().ok_or("--history needs a number of seconds")?;
+                history_seconds = value.parse().map_err(|_| format!("bad history length {}", value))?;
+                if history_seconds.is_nan() || history_seconds <= 0. {
+                    return Err(format!("bad history length {}", value));
                 }
             }
-        }
-
-        let get_text_from_coords = |x, y| {
-            let (x, y) = match self.stick_pos_format {
-                StickPosFormat::Integer => (x as f64, y as f64),
-                StickPosFormat::Decimal => (x as f64 / 80., y as f64 / 80.),
-            };
-            let mx = if x < 0.0 {'-'} else {' '};
-            let my = if y < 0.0 {'-'} else {' '};
-            format!("({}{:<6}, {}{:<6})", mx, x.abs(), my, y.abs())
+            _ => return Err(format!("unknown argument {}", arg)),
         };
-
-        let (x, y) = self.get_controller().stick_clamp();
-        let (real_x, real_y) = self.get_controller().stick_pos();
-        draw_text(ctx, get_text_from_coords(x, y), 0., 0., Color::WHITE)?;
-        if (real_x, real_y) != (x, y) {
-            draw_text(ctx, get_text_from_coords(real_x, real_y), 0., 15., Color::RED)?;
-        }
-
-        let (raw_x, raw_y) = self.get_controller().stick_raw();
-        draw_text(ctx, format!("({:<5}, {:<5})", raw_x, raw_y), 0., 30., Color::BLUE)?;
-
-        let (c_x, c_y) = self.get_controller().c_stick_clamp();
-        let (real_c_x, real_c_y) = self.get_controller().c_stick_pos();
-        draw_text(ctx, get_text_from_coords(c_x, c_y), 400., 400., Color::from_rgb(0xff, 0xff, 0x00))?;
-        if (real_c_x, real_c_y) != (c_x, c_y) {
-            draw_text(ctx, get_text_from_coords(real_c_x, real_c_y), 400., 415., Color::from_rgb(0xc0, 0xc0, 0x00))?;
-        }
-
-        draw_text(ctx, format!("(fpx: {})", ggez::timer::fps(ctx)), 250., 0., Color::WHITE)?;
-
-        draw_text(ctx, format!("L: {}", self.get_controller().l_analog()), 0., 45., Color::WHITE)?;
-        draw_text(ctx, format!("R: {}", self.get_controller().r_analog()), 0., 60., Color::WHITE)?;
-
-        let mut y_pos = 700.;
-        for sequence in self.completed_sequences.iter().rev().take(4) {
-            let num_lines = draw_completed_sequence(ctx, sequence, 1100., y_pos)?;
-            y_pos += (num_lines + 1) as f32 * 15.;
-        }
-
-        graphics::present(ctx)
     }
-}
-
-//returns line count
-fn draw_completed_sequence(ctx: &mut Context, sequence: &CompletedSequence, x: f32, y: f32) -> GameResult<i32> {
-    let (name, actions, success_rate) = sequence;
-    let name_text = format!("{}, ", name);
-    let chance_text = format!("{}%", success_rate * 100.);
-    let chance_color = match *success_rate {
-        x if x == 1. => Color::GREEN,
-        x if x == 0. => Color::RED,
-        x if x > 0. && x < 1. => ORANGE,
-        _ => Color::MAGENTA,
-    };
-    draw_colored_text(ctx, vec![name_text, "chance of success: ".to_string(), chance_text], x, y, &[Color::YELLOW, Color::CYAN, chance_color])?;
-
-    let dot_start_y = y + (15*actions.len() + 15) as f32;
-    let mut dot_x_pos = x;
-    const FRAME_LENGTH: f32 = 30.;
-    for i in 0..15 {
-        let x = x + i as f32 * FRAME_LENGTH;
-        let points = [[x, dot_start_y + 3.], [x, dot_start_y + 12.]];
-        let line = graphics::Mesh::new_line(ctx, &points, 1., Color::from_rgb(128, 128, 128))?;
-        graphics::draw(ctx, &line, DrawParam::new())?;
+    if let Some(path) = replay_path {
+        source = Box::new(session::ReplaySource { path, speed });
     }
-
-    for (i, (input, since_last, success)) in actions.iter().enumerate() {
-        let color = match success {
-            ActionSuccess::EarlyMiss => Color::from_rgb(255, 0, 128),
-            ActionSuccess::Early => Color::MAGENTA,
-            ActionSuccess::Success => Color::CYAN,
-            ActionSuccess::Late => ORANGE,
-            ActionSuccess::LateMiss => Color::RED,
-        };
-        let text = format!("{}, time: {:.3} frames ({} ms) {}", input.to_string(), duration_to_frame_count(*since_last), since_last.as_millis(), success);
-        draw_text(ctx, text, x, y + (15*(i + 1)) as f32, color)?;
-
-        dot_x_pos += duration_to_frame_count(*since_last) as f32 * FRAME_LENGTH;
-        let dot = graphics::Mesh::new_circle(ctx, DrawMode::fill(), [dot_x_pos, dot_start_y + 7.], 3., 1., color)?;
-        graphics::draw(ctx, &dot, DrawParam::new())?;
+    #[cfg(not(feature = "gui"))]
+    if headless.is_none() {
+        return Err("gcviewer was built without the window, it needs --headless".to_string())
     }
-    Ok((actions.len() + 2).try_into().unwrap())
+    Ok(Options { source, record_path, rumble, headless, profile, history_length: Duration::from_secs_f64(history_seconds), planes_path })
 }
 
-fn duration_to_frame_count(duration: Duration) -> f64 {
-    return duration.as_micros() as f64 / (1_000_000. / 60.)
+fn built_in_planes() -> Vec<PlaneWithZones> {
+    vec![PlaneWithZones::default_plane(), PlaneWithZones::angle_plane()]
 }
 
-fn draw_colored_text<F>(ctx: &mut Context, texts: Vec<F>, x: f32, y: f32, colors: &[Color]) -> GameResult<()>
-where F: Into<TextFragment>
-{
-    let mut x = x;
-    for (text_str, color) in texts.into_iter().zip(colors.iter()) {
-        let text = Text::new(TextFragment::new(text_str).color(*color));
-        graphics::draw(ctx, &text, DrawParam::new().dest([x, y]))?;
-        x += text.width(ctx);
+/// A file that isn't there yet gets the built in planes, to start editing from.
+fn load_or_create_library(path: &str) -> Result<ZoneLibrary, Box<dyn std::error::Error>> {
+    if !std::path::Path::new(path).exists() {
+        let mut library = ZoneLibrary::new(path, built_in_planes());
+        library.path = Some(path.to_string());
+        plane_config::save_library(path, &library)?;
+        eprintln!("wrote the built in zone planes to {}", path);
+        return Ok(library)
     }
-    Ok(())
+    plane_config::load_library(path)
 }
 
-fn draw_text<F>(ctx: &mut Context, text: F, x: f32, y: f32, color: Color) -> GameResult<()>
-where F: Into<TextFragment>
-{
-    let coords_text = TextFragment::new(text).color(color);
-    graphics::draw(ctx, &Text::new(coords_text), DrawParam::new().dest([x, y]))?;
-    Ok(())
-}
-
-fn reset_graphics(ctx: &mut Context) -> GameResult<()> {
-    graphics::set_canvas(ctx, None);
-    graphics::set_screen_coordinates(ctx, Rect::new(0., 0., WIDTH as f32, HEIGHT as f32))?;
-    Ok(())
-}
@@ -2,7 +2,9 @@ use std::time::{Duration, Instant};
 
 use ggez::{Context, GameResult, graphics::{self, Canvas, Color, DrawMode, DrawParam, Mesh, Rect, get_window_color_format}};
 
-use crate::{reset_graphics, zones};
+use gcviewer::zones;
+
+use crate::window::reset_graphics;
 
 pub trait Scope {
     type Data;
@@ -247,7 +249,7 @@ impl Scope for Oscilloscope {
                 self.scope_start_time = time;
             }
 
-            crate::reset_graphics(ctx)?;
+            reset_graphics(ctx)?;
         }
 
         self.last_point = Some(ScopePoint { point: new_point, time } );
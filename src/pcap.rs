@@ -0,0 +1,197 @@
+use std::convert::TryInto;
+use std::time::Duration;
+
+use crate::gc_adapter::UsbmonPacket;
+use crate::session::SessionPoll;
+
+//usbmon link types, the mmapped one has the full 64 byte header, the other only 48
+const LINKTYPE_USB_LINUX: u32 = 189;
+const LINKTYPE_USB_LINUX_MMAPPED: u32 = 220;
+
+const PCAPNG_SECTION_HEADER: u32 = 0x0A0D0D0A;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 1;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+
+pub fn is_pcap(data: &[u8]) -> bool {
+    match data.get(0..4) {
+        Some(magic) => {
+            let magic = u32::from_le_bytes(magic.try_into().unwrap());
+            [0xa1b2c3d4, 0xd4c3b2a1, 0xa1b23c4d, 0x4d3cb2a1, PCAPNG_SECTION_HEADER].contains(&magic)
+        }
+        None => false,
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn u16_at(&self, i: usize) -> Option<u16> {
+        let bytes = self.data.get(i..i + 2)?.try_into().ok()?;
+        Some(if self.big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    }
+
+    fn u32_at(&self, i: usize) -> Option<u32> {
+        let bytes = self.data.get(i..i + 4)?.try_into().ok()?;
+        Some(if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    }
+}
+
+/// Keeps the adapter polls out of a capture's packets, and gives every
+/// adapter (bus and device number) an id in the order they show up.
+struct PollCollector {
+    polls: Vec<SessionPoll>,
+    adapters: Vec<(u16, u8)>,
+    first_time: Option<Duration>,
+}
+
+impl PollCollector {
+    fn add_packet(&mut self, time: Duration, link_type: u32, packet: &[u8]) {
+        let header_len = match link_type {
+            LINKTYPE_USB_LINUX => 48,
+            LINKTYPE_USB_LINUX_MMAPPED => 64,
+            _ => return,
+        };
+        let header = match UsbmonPacket::from_bytes(packet) {
+            Some(header) => header,
+            None => return,
+        };
+        //completed interrupt in transfers only, like try_snoop_usb
+        if header.type_ as u8 != b'C' || header.xfer_type != 1 || header.epnum & 0x80 == 0 {
+            return
+        }
+        let data = match packet.get(header_len..header_len + 37) {
+            Some(data) if header.len_cap >= 37 && data[0] == 0x21 => data,
+            _ => return,
+        };
+
+        let device = (header.busnum, header.devnum);
+        let adapter = match self.adapters.iter().position(|known| *known == device) {
+            Some(adapter) => adapter,
+            None => {
+                self.adapters.push(device);
+                self.adapters.len() - 1
+            }
+        };
+        let first_time = *self.first_time.get_or_insert(time);
+        self.polls.push((time.saturating_sub(first_time), adapter, data.try_into().unwrap()));
+    }
+}
+
+/// Reads every adapter poll out of a .pcap or .pcapng usbmon capture.
+pub fn read_pcap(data: &[u8]) -> Result<Vec<SessionPoll>, Box<dyn std::error::Error>> {
+    let mut collector = PollCollector { polls: Vec::new(), adapters: Vec::new(), first_time: None };
+    let magic = u32::from_le_bytes(data.get(0..4).ok_or("file too short")?.try_into()?);
+    if magic == PCAPNG_SECTION_HEADER {
+        read_pcapng(data, &mut collector)?;
+    }
+    else {
+        read_classic_pcap(data, &mut collector)?;
+    }
+    eprintln!("found {} polls from {} adapters in capture", collector.polls.len(), collector.adapters.len());
+    //pcapng doesn't have to be in order
+    collector.polls.sort_by_key(|(time, _, _)| *time);
+    Ok(collector.polls)
+}
+
+fn read_classic_pcap(data: &[u8], collector: &mut PollCollector) -> Result<(), Box<dyn std::error::Error>> {
+    let (big_endian, nanos) = match u32::from_le_bytes(data[0..4].try_into()?) {
+        0xa1b2c3d4 => (false, false),
+        0xd4c3b2a1 => (true, false),
+        0xa1b23c4d => (false, true),
+        0x4d3cb2a1 => (true, true),
+        _ => Err("not a pcap file")?,
+    };
+    let reader = Reader { data, big_endian };
+    let link_type = reader.u32_at(20).ok_or("pcap header too short")?;
+    if link_type != LINKTYPE_USB_LINUX && link_type != LINKTYPE_USB_LINUX_MMAPPED {
+        Err(format!("capture has link type {}, not usbmon", link_type))?;
+    }
+
+    let mut pos = 24;
+    while let (Some(seconds), Some(fraction), Some(len)) = (reader.u32_at(pos), reader.u32_at(pos + 4), reader.u32_at(pos + 8)) {
+        let start = pos + 16;
+        let end = start + len as usize;
+        let packet = data.get(start..end).ok_or("capture is cut off")?;
+        let fraction = if nanos { Duration::from_nanos(fraction as u64) } else { Duration::from_micros(fraction as u64) };
+        collector.add_packet(Duration::from_secs(seconds as u64) + fraction, link_type, packet);
+        pos = end;
+    }
+    Ok(())
+}
+
+fn read_pcapng(data: &[u8], collector: &mut PollCollector) -> Result<(), Box<dyn std::error::Error>> {
+    let mut reader = Reader { data, big_endian: false };
+    //link type and timestamp units per second, for each interface in the current section
+    let mut interfaces: Vec<(u32, u64)> = Vec::new();
+
+    let mut pos = 0;
+    while pos + 12 <= data.len() {
+        if u32::from_le_bytes(data[pos..pos + 4].try_into()?) == PCAPNG_SECTION_HEADER {
+            reader.big_endian = match data.get(pos + 8..pos + 12) {
+                Some([0x1a, 0x2b, 0x3c, 0x4d]) => true,
+                Some([0x4d, 0x3c, 0x2b, 0x1a]) => false,
+                _ => Err("bad pcapng byte order magic")?,
+            };
+            interfaces.clear();
+        }
+        let block_type = reader.u32_at(pos).ok_or("capture is cut off")?;
+        let block_len = reader.u32_at(pos + 4).ok_or("capture is cut off")? as usize;
+        if block_len < 12 || pos + block_len > data.len() {
+            Err("bad pcapng block length")?;
+        }
+        let body = pos + 8;
+        let body_end = pos + block_len - 4;
+
+        match block_type {
+            PCAPNG_INTERFACE_DESCRIPTION => {
+                let link_type = reader.u16_at(body).ok_or("capture is cut off")? as u32;
+                interfaces.push((link_type, interface_resolution(&reader, body + 8, body_end)));
+            }
+            PCAPNG_ENHANCED_PACKET => {
+                let interface = reader.u32_at(body).ok_or("capture is cut off")? as usize;
+                let (link_type, units_per_second) = *interfaces.get(interface).ok_or("packet for unknown interface")?;
+                let high = reader.u32_at(body + 4).ok_or("capture is cut off")? as u64;
+                let low = reader.u32_at(body + 8).ok_or("capture is cut off")? as u64;
+                let len = reader.u32_at(body + 12).ok_or("capture is cut off")? as usize;
+                let packet = data.get(body + 20..body + 20 + len).ok_or("capture is cut off")?;
+
+                let timestamp = (high << 32) | low;
+                let time = Duration::from_secs(timestamp / units_per_second)
+                    + Duration::from_nanos((timestamp % units_per_second) * 1_000_000_000 / units_per_second);
+                collector.add_packet(time, link_type, packet);
+            }
+            //simple packets don't have timestamps, and nothing else has packets
+            _ => {}
+        }
+        pos += block_len;
+    }
+    Ok(())
+}
+
+/// Reads the if_tsresol option out of an interface block, defaults to microseconds.
+fn interface_resolution(reader: &Reader, mut pos: usize, end: usize) -> u64 {
+    const OPT_END: u16 = 0;
+    const IF_TSRESOL: u16 = 9;
+    while pos + 4 <= end {
+        let (code, len) = match (reader.u16_at(pos), reader.u16_at(pos + 2)) {
+            (Some(code), Some(len)) => (code, len as usize),
+            _ => break,
+        };
+        if code == OPT_END {
+            break
+        }
+        if code == IF_TSRESOL && len == 1 {
+            if let Some(resolution) = reader.data.get(pos + 4) {
+                let exponent = (resolution & 0x7f) as u32;
+                let base: u64 = if resolution & 0x80 == 0 { 10 } else { 2 };
+                return base.checked_pow(exponent).unwrap_or(1_000_000)
+            }
+        }
+        //options are padded to 4 bytes
+        pos += 4 + len.div_ceil(4) * 4;
+    }
+    1_000_000
+}
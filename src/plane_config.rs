@@ -0,0 +1,332 @@
+use std::fs;
+
+use crate::zone_library::ZoneLibrary;
+use crate::zones::{Circle, PlaneWithZones, Polygon, SquareZone, Wedge, Zone, ZoneColor, ZoneTrait, C_STICK_PLANE};
+
+/*
+planes file layout, one section per plane, then one zone per line in priority
+order (a point gets the colour of the first zone it's in):
+    [name]
+    zone name: shape, background colour, trail colour
+shapes:
+    square min_x max_x min_y max_y
+    wedge min_degrees max_degrees min_magnitude max_magnitude
+    circle x y radius
+    polygon x y x y x y...
+and can be joined up with or, and, minus, going left to right unless there are brackets:
+    [melee]
+    deadzone: square -22 22 -22 22, 404040, c0c0c0
+    f smash: square 64 127 -128 127 or square -128 -64 -128 127, 400040, 8000ff
+    up tilt: square -22 22 23 127 minus (square -128 127 53 127 or circle 0 0 8), 006000, 00ff00
+the line's name is for the legend, and the zone and all its parts are called that too
+unless a name in quotes comes after a shape or brackets, which names just that part:
+    wavedash: wedge 180 235 0 255 "wavedash left", 004040, 00ffff
+    f smash: square 64 127 -128 127 "f smash right" or square -128 -64 -128 127 "f smash left", 400040, 8000ff
+a plane called [c stick] is what the c stick gets drawn with, the rest are for the control stick
+*/
+
+/// A missing file is an error here, unlike profiles, since it was asked for on the command line.
+pub fn load_planes(path: &str) -> Result<Vec<PlaneWithZones>, Box<dyn std::error::Error>> {
+    let planes = parse_planes(&fs::read_to_string(path)?)?;
+    if planes.is_empty() {
+        Err(format!("no planes in {}", path))?;
+    }
+    Ok(planes)
+}
+
+pub fn save_planes(path: &str, planes: &[PlaneWithZones]) -> std::io::Result<()> {
+    fs::write(path, planes_to_string(planes))
+}
+
+/// The file's planes, with the `[c stick]` one (if it's there) for the c stick.
+pub fn load_library(path: &str) -> Result<ZoneLibrary, Box<dyn std::error::Error>> {
+    let mut planes = load_planes(path)?;
+    let c_stick = planes.iter().position(|plane| plane.name == C_STICK_PLANE).map(|index| planes.remove(index));
+    if planes.is_empty() {
+        Err(format!("no planes in {} besides the c stick one", path))?;
+    }
+    let mut library = ZoneLibrary::new(path, planes);
+    library.path = Some(path.to_string());
+    if let Some(c_stick) = c_stick {
+        library.c_stick = c_stick;
+    }
+    Ok(library)
+}
+
+pub fn save_library(path: &str, library: &ZoneLibrary) -> std::io::Result<()> {
+    let text = planes_to_string(&library.planes) + &planes_to_string(std::slice::from_ref(&library.c_stick));
+    fs::write(path, text)
+}
+
+pub fn planes_to_string(planes: &[PlaneWithZones]) -> String {
+    let mut text = String::new();
+    for plane in planes {
+        text += &format!("[{}]\n", plane.name);
+        for (zone, color) in &plane.zones {
+            text += &format!("{}: {}, {}, {}\n", color.name, zone_to_string(zone, &color.name), color_to_string(color.bg_color), color_to_string(color.fg_color));
+        }
+    }
+    text
+}
+
+/// The zone as it goes on a line called `line_name`, anything called something else gets its name in quotes.
+pub fn zone_to_string(zone: &Zone, line_name: &str) -> String {
+    match zone.get_name() == line_name {
+        true => shape_to_string(zone, line_name),
+        false => part_to_string(zone, line_name),
+    }
+}
+
+fn part_to_string(zone: &Zone, line_name: &str) -> String {
+    //anything made of other zones gets brackets when it's inside something else or has its own name
+    let text = match zone {
+        Zone::Union(_) | Zone::Intersection(_) | Zone::Difference(_) => format!("({})", shape_to_string(zone, line_name)),
+        _ => shape_to_string(zone, line_name),
+    };
+    match zone.get_name() == line_name {
+        true => text,
+        false => format!("{} \"{}\"", text, zone.get_name()),
+    }
+}
+
+fn shape_to_string(zone: &Zone, line_name: &str) -> String {
+    let part = |zone: &Zone| part_to_string(zone, line_name);
+    match zone {
+        Zone::SquareZone(square) => format!("square {} {} {} {}", square.min_x, square.max_x, square.min_y, square.max_y),
+        Zone::Wedge(wedge) => format!("wedge {} {} {} {}", wedge.min_angle as f64 / 10., wedge.max_angle as f64 / 10., wedge.min_magnitude, wedge.max_magnitude),
+        Zone::Circle(circle) => format!("circle {} {} {}", circle.center.0, circle.center.1, circle.radius),
+        Zone::Polygon(polygon) => {
+            let points: Vec<String> = polygon.points.iter().map(|(x, y)| format!("{} {}", x, y)).collect();
+            format!("polygon {}", points.join(" "))
+        }
+        Zone::Union(set) => set.zones.iter().map(part).collect::<Vec<_>>().join(" or "),
+        Zone::Intersection(set) => set.zones.iter().map(part).collect::<Vec<_>>().join(" and "),
+        Zone::Difference(difference) => format!("{} minus {}", part(&difference.zone), part(&difference.minus)),
+    }
+}
+
+fn color_to_string(color: (u8, u8, u8)) -> String {
+    format!("{:02x}{:02x}{:02x}", color.0, color.1, color.2)
+}
+
+fn parse_color(text: &str) -> Option<(u8, u8, u8)> {
+    let text = text.trim().trim_start_matches('#');
+    if text.len() != 6 {
+        return None
+    }
+    let value = u32::from_str_radix(text, 16).ok()?;
+    Some(((value >> 16) as u8, (value >> 8) as u8, value as u8))
+}
+
+pub fn parse_planes(text: &str) -> Result<Vec<PlaneWithZones>, String> {
+    let mut planes: Vec<PlaneWithZones> = Vec::new();
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue
+        }
+        let bad_line = |why: &str| format!("bad plane line {}, {}: {}", line_number + 1, why, line);
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            planes.push(PlaneWithZones::new(name));
+            continue
+        }
+        let plane = planes.last_mut().ok_or_else(|| bad_line("zone before any [plane]"))?;
+        let (name, rest) = line.split_once(':').ok_or_else(|| bad_line("no zone name"))?;
+        let parts: Vec<&str> = rest.split(',').collect();
+        let (shape, bg_color, fg_color) = match parts.as_slice() {
+            [shape, bg_color, fg_color] => (shape, bg_color, fg_color),
+            _ => return Err(bad_line("needs a shape and two colours")),
+        };
+        let bg_color = parse_color(bg_color).ok_or_else(|| bad_line("bad background colour"))?;
+        let fg_color = parse_color(fg_color).ok_or_else(|| bad_line("bad trail colour"))?;
+        //zones only have static names, there aren't many and they live as long as the program does anyway
+        let name: &'static str = Box::leak(name.trim().to_string().into_boxed_str());
+        let zone = parse_zone(shape, name).map_err(|why| bad_line(&why))?;
+        plane.add_zone(zone, ZoneColor { name: name.to_string(), bg_color, fg_color });
+    }
+    Ok(planes)
+}
+
+/// Parses a shape, every part of it without a name in quotes gets `name`.
+pub fn parse_zone(text: &str, name: &'static str) -> Result<Zone, String> {
+    let tokens = split_tokens(text)?;
+    let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+    let mut position = 0;
+    let zone = parse_expression(&tokens, &mut position, name)?;
+    match tokens.get(position) {
+        None => Ok(zone),
+        Some(token) => Err(format!("didn't expect {}", token)),
+    }
+}
+
+//brackets are tokens even without spaces around them, and names in quotes keep their quotes and spaces
+fn split_tokens(text: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' | ')' => tokens.push(c.to_string()),
+            '"' => {
+                let mut name = String::from('"');
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => name.push(c),
+                        None => return Err("missing closing quote".to_string()),
+                    }
+                }
+                name.push('"');
+                tokens.push(name);
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        break
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(word);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+//a name in quotes straight after a shape or brackets
+fn parse_name(tokens: &[&str], position: &mut usize, zone: &mut Zone) {
+    if let Some(name) = tokens.get(*position).and_then(|token| token.strip_prefix('"')).and_then(|token| token.strip_suffix('"')) {
+        zone.set_name(Box::leak(name.to_string().into_boxed_str()));
+        *position += 1;
+    }
+}
+
+fn parse_expression(tokens: &[&str], position: &mut usize, name: &'static str) -> Result<Zone, String> {
+    let mut zone = parse_term(tokens, position, name)?;
+    //a or b or c is one union, not a union in a union, unless a or b was in brackets
+    let mut last_operator = None;
+    while let Some(operator) = tokens.get(*position).copied() {
+        if operator == ")" {
+            break
+        }
+        *position += 1;
+        let next = parse_term(tokens, position, name)?;
+        zone = match (operator, zone) {
+            ("or", Zone::Union(mut set)) if last_operator == Some("or") => {
+                set.zones.push(next);
+                Zone::Union(set)
+            }
+            ("or", zone) => Zone::union(name, vec![zone, next]),
+            ("and", Zone::Intersection(mut set)) if last_operator == Some("and") => {
+                set.zones.push(next);
+                Zone::Intersection(set)
+            }
+            ("and", zone) => Zone::intersection(name, vec![zone, next]),
+            ("minus", zone) => Zone::difference(name, zone, next),
+            (operator, _) => return Err(format!("{} isn't or, and or minus", operator)),
+        };
+        last_operator = Some(operator);
+    }
+    Ok(zone)
+}
+
+fn parse_term(tokens: &[&str], position: &mut usize, name: &'static str) -> Result<Zone, String> {
+    let mut zone = parse_shape(tokens, position, name)?;
+    parse_name(tokens, position, &mut zone);
+    Ok(zone)
+}
+
+fn parse_shape(tokens: &[&str], position: &mut usize, name: &'static str) -> Result<Zone, String> {
+    let shape = *tokens.get(*position).ok_or("shape missing")?;
+    *position += 1;
+    if shape == "(" {
+        let zone = parse_expression(tokens, position, name)?;
+        if tokens.get(*position) != Some(&")") {
+            return Err("missing )".to_string())
+        }
+        *position += 1;
+        return Ok(zone)
+    }
+
+    let mut numbers: Vec<f64> = vec![];
+    while let Some(Ok(number)) = tokens.get(*position).map(|token| token.parse::<f64>()) {
+        numbers.push(number);
+        *position += 1;
+    }
+    let stick = |value: f64| -> Result<i8, String> {
+        if value.fract() != 0. || !(-128. ..=127.).contains(&value) {
+            return Err(format!("{} isn't a stick value", value))
+        }
+        Ok(value as i8)
+    };
+    let magnitude = |value: f64| -> Result<u8, String> {
+        if value.fract() != 0. || !(0. ..=255.).contains(&value) {
+            return Err(format!("{} isn't a magnitude", value))
+        }
+        Ok(value as u8)
+    };
+    let angle = |value: f64| -> Result<u16, String> {
+        if !(0. ..360.).contains(&value) {
+            return Err(format!("{} isn't an angle from 0 to 360", value))
+        }
+        Ok((value * 10.).round() as u16 % 3600)
+    };
+    match (shape, numbers.as_slice()) {
+        ("square", [min_x, max_x, min_y, max_y]) => Ok(Zone::SquareZone(SquareZone {
+            min_x: stick(*min_x)?, max_x: stick(*max_x)?, min_y: stick(*min_y)?, max_y: stick(*max_y)?, name
+        })),
+        ("wedge", [min_angle, max_angle, min_magnitude, max_magnitude]) => Ok(Zone::Wedge(Wedge {
+            min_angle: angle(*min_angle)?, max_angle: angle(*max_angle)?, min_magnitude: magnitude(*min_magnitude)?, max_magnitude: magnitude(*max_magnitude)?, name
+        })),
+        ("circle", [x, y, radius]) => Ok(Zone::Circle(Circle {
+            center: (stick(*x)?, stick(*y)?), radius: magnitude(*radius)?, name
+        })),
+        ("polygon", points) if points.len() >= 6 && points.len() % 2 == 0 => {
+            let points = points.chunks(2).map(|point| Ok((stick(point[0])?, stick(point[1])?))).collect::<Result<Vec<_>, String>>()?;
+            Ok(Zone::Polygon(Polygon { points: Box::leak(points.into_boxed_slice()), name }))
+        }
+        ("square" | "wedge" | "circle", _) => Err(format!("wrong number of values for {}", shape)),
+        ("polygon", _) => Err("polygons need at least 3 x y pairs".to_string()),
+        _ => Err(format!("unknown shape {}", shape)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zone_library;
+
+    #[test]
+    fn planes_survive_the_file() {
+        let mut planes = vec![PlaneWithZones::default_plane(), PlaneWithZones::angle_plane(), PlaneWithZones::c_stick_plane()];
+        for library in zone_library::character_libraries() {
+            planes.extend(library.planes);
+        }
+        assert_eq!(parse_planes(&planes_to_string(&planes)), Ok(planes));
+    }
+
+    #[test]
+    fn parts_keep_their_names() {
+        let planes = parse_planes("[p]\nsmash: (square 64 127 -128 127 \"right\" or square -128 -64 -128 127) \"f smash\", 400040, 8000ff\n").unwrap();
+        let (zone, color) = &planes[0].zones[0];
+        assert_eq!(color.name, "smash");
+        assert_eq!(zone.get_name(), "f smash");
+        match zone {
+            Zone::Union(set) => {
+                assert_eq!(set.zones[0].get_name(), "right");
+                assert_eq!(set.zones[1].get_name(), "smash");
+            }
+            _ => panic!("not a union: {:?}", zone),
+        }
+    }
+
+    #[test]
+    fn unnamed_lines_name_everything() {
+        let planes = parse_planes("[p]\nup tilt: square -22 22 23 127 minus (square -128 127 53 127 or circle 0 0 8), 006000, 00ff00\n").unwrap();
+        let line = planes_to_string(&planes);
+        assert_eq!(line, "[p]\nup tilt: square -22 22 23 127 minus (square -128 127 53 127 or circle 0 0 8), 006000, 00ff00\n");
+        assert!(parse_planes("[p]\nx: square 0 1 0 1 \"open, 000000, ffffff\n").is_err());
+    }
+}
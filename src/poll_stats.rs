@@ -0,0 +1,139 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+pub const BUCKET_WIDTH: Duration = Duration::from_micros(500);
+//the last bucket is everything from 10ms up
+pub const BUCKET_COUNT: usize = 21;
+
+//how many intervals the rate and median are worked out from
+const RECENT_LEN: usize = 1000;
+//don't call anything dropped until we know what normal looks like
+const MIN_SAMPLES: usize = 50;
+
+/// Tracks the time between polls for one adapter, to check overclocks.
+pub struct PollStats {
+    last_time: Option<Instant>,
+    recent: VecDeque<Duration>,
+    pub histogram: [u32; BUCKET_COUNT],
+    pub polls: u64,
+    pub dropped: u64,
+    pub duplicated: u64,
+    pub worst_gap: Duration,
+}
+
+impl PollStats {
+    pub fn new() -> Self {
+        Self {
+            last_time: None,
+            recent: VecDeque::new(),
+            histogram: [0; BUCKET_COUNT],
+            polls: 0,
+            dropped: 0,
+            duplicated: 0,
+            worst_gap: Duration::ZERO,
+        }
+    }
+
+    pub fn record(&mut self, time: Instant) {
+        self.polls += 1;
+        let last_time = self.last_time.replace(time);
+        let interval = match last_time {
+            Some(last_time) => time.saturating_duration_since(last_time),
+            None => return,
+        };
+
+        let expected = self.median_interval();
+        //captures can repeat timestamps, and nothing can be judged against a zero interval
+        if self.recent.len() >= MIN_SAMPLES && !expected.is_zero() {
+            //a poll that took twice as long means one went missing, and
+            //two arriving right after each other means one got held back
+            if interval > expected.mul_f64(1.5) {
+                self.dropped += (interval.as_secs_f64() / expected.as_secs_f64()).round() as u64 - 1;
+            }
+            else if interval < expected / 2 {
+                self.duplicated += 1;
+            }
+        }
+
+        let bucket = ((interval.as_micros() / BUCKET_WIDTH.as_micros()) as usize).min(BUCKET_COUNT - 1);
+        self.histogram[bucket] += 1;
+        self.worst_gap = self.worst_gap.max(interval);
+        self.recent.push_back(interval);
+        if self.recent.len() > RECENT_LEN {
+            self.recent.pop_front();
+        }
+    }
+
+    pub fn median_interval(&self) -> Duration {
+        let mut sorted: Vec<_> = self.recent.iter().collect();
+        sorted.sort();
+        sorted.get(sorted.len() / 2).map(|median| **median).unwrap_or(Duration::ZERO)
+    }
+
+    /// Polls per second over the recent intervals, 125 on a stock adapter.
+    pub fn rate(&self) -> f64 {
+        let total: Duration = self.recent.iter().sum();
+        if total.is_zero() {
+            return 0.
+        }
+        self.recent.len() as f64 / total.as_secs_f64()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        csv += &format!("# polls,{}\n", self.polls);
+        csv += &format!("# rate_hz,{:.2}\n", self.rate());
+        csv += &format!("# median_interval_ms,{:.3}\n", self.median_interval().as_secs_f64() * 1000.);
+        csv += &format!("# dropped,{}\n", self.dropped);
+        csv += &format!("# duplicated,{}\n", self.duplicated);
+        csv += &format!("# worst_gap_ms,{:.3}\n", self.worst_gap.as_secs_f64() * 1000.);
+        csv += "interval_start_ms,interval_end_ms,count\n";
+        for (i, count) in self.histogram.iter().enumerate() {
+            let start = (BUCKET_WIDTH * i as u32).as_secs_f64() * 1000.;
+            let end = if i == BUCKET_COUNT - 1 { "".to_string() } else { format!("{}", (BUCKET_WIDTH * (i + 1) as u32).as_secs_f64() * 1000.) };
+            csv += &format!("{},{},{}\n", start, end, count);
+        }
+        csv
+    }
+}
+
+impl Default for PollStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_timestamps_dont_overflow() {
+        let mut stats = PollStats::new();
+        let start = Instant::now();
+        for i in 0..200 {
+            //three polls at each time, so most intervals are zero
+            stats.record(start + Duration::from_millis(8 * (i / 3)));
+        }
+        assert_eq!(stats.median_interval(), Duration::ZERO);
+        stats.record(start + Duration::from_secs(1));
+        assert_eq!(stats.dropped, 0);
+    }
+
+    #[test]
+    fn counts_dropped_polls() {
+        let mut stats = PollStats::new();
+        let start = Instant::now();
+        for i in 0..100 {
+            stats.record(start + Duration::from_millis(8 * i));
+        }
+        //three polls missing
+        stats.record(start + Duration::from_millis(8 * 103));
+        assert_eq!(stats.dropped, 3);
+        assert_eq!(stats.duplicated, 0);
+    }
+}
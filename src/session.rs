@@ -0,0 +1,125 @@
+use std::convert::TryInto;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::gc_adapter::{AdapterEvent, ControllerPoll, InputSource};
+
+/*
+session file layout, everything little endian:
+    "GCVS" magic, 1 byte version
+    then one record per poll:
+        u64 microseconds since the first poll
+        u8 adapter id (only in version 2 and up)
+        37 byte raw adapter buffer
+*/
+pub const MAGIC: &[u8; 4] = b"GCVS";
+pub const VERSION: u8 = 2;
+const HEADER_LEN: usize = 5;
+
+/// Time since the first poll, adapter id and the raw adapter buffer.
+pub type SessionPoll = (Duration, usize, [u8; 37]);
+
+pub fn read_session(data: &[u8]) -> Result<Vec<SessionPoll>, Box<dyn std::error::Error>> {
+    if data.len() < HEADER_LEN || &data[0..4] != MAGIC {
+        Err("not a gcviewer session file")?;
+    }
+    let has_adapter_id = match data[4] {
+        1 => false,
+        2 => true,
+        version => Err(format!("unsupported session file version {}", version))?,
+    };
+    let record_len = if has_adapter_id { 8 + 1 + 37 } else { 8 + 37 };
+    let mut polls = Vec::new();
+    for record in data[HEADER_LEN..].chunks_exact(record_len) {
+        let micros = u64::from_le_bytes(record[0..8].try_into()?);
+        let adapter = if has_adapter_id { record[8] as usize } else { 0 };
+        let buffer = record[(record_len - 37)..].try_into()?;
+        polls.push((Duration::from_micros(micros), adapter, buffer));
+    }
+    Ok(polls)
+}
+
+/// Loads a session file or a usbmon pcap/pcapng capture.
+pub fn load_polls(path: &str) -> Result<Vec<SessionPoll>, Box<dyn std::error::Error>> {
+    let data = fs::read(path)?;
+    if crate::pcap::is_pcap(&data) {
+        crate::pcap::read_pcap(&data)
+    }
+    else {
+        read_session(&data)
+    }
+}
+
+/// Appends every poll to a session file as it comes in.
+pub struct SessionWriter {
+    file: BufWriter<File>,
+    start: Option<Instant>,
+    last_flush: Instant,
+}
+
+impl SessionWriter {
+    pub fn create(path: &str) -> std::io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&[VERSION])?;
+        Ok(Self { file, start: None, last_flush: Instant::now() })
+    }
+
+    pub fn write_poll(&mut self, poll: &ControllerPoll) -> std::io::Result<()> {
+        let start = *self.start.get_or_insert(poll.time);
+        let micros = poll.time.saturating_duration_since(start).as_micros() as u64;
+        self.file.write_all(&micros.to_le_bytes())?;
+        self.file.write_all(&[poll.adapter as u8])?;
+        self.file.write_all(&poll.buffer)?;
+
+        //ggez might exit without dropping us, so don't keep too much buffered
+        if self.last_flush.elapsed() > Duration::from_secs(1) {
+            self.file.flush()?;
+            self.last_flush = Instant::now();
+        }
+        Ok(())
+    }
+
+    /// Call when recording stops or before quitting, so the last polls make it to the file.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.last_flush = Instant::now();
+        self.file.flush()
+    }
+}
+
+/// Plays back a recorded session file or capture, `speed` of 1 is real time.
+/// Poll times keep the recorded spacing so frame timings stay correct
+/// when slowed down or sped up.
+pub struct ReplaySource {
+    pub path: String,
+    pub speed: f64,
+}
+
+impl InputSource for ReplaySource {
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    fn run(&mut self, sender: &Sender<AdapterEvent>) -> Result<(), Box<dyn std::error::Error>> {
+        let polls = load_polls(&self.path)?;
+        eprintln!("replaying {} polls from {}", polls.len(), self.path);
+        let start = Instant::now();
+        let mut connected = Vec::new();
+        for (offset, adapter, buffer) in polls {
+            if !connected.contains(&adapter) {
+                sender.send(AdapterEvent::Connected(adapter))?;
+                connected.push(adapter);
+            }
+            let send_time = start + offset.div_f64(self.speed);
+            let now = Instant::now();
+            if send_time > now {
+                thread::sleep(send_time - now);
+            }
+            sender.send(AdapterEvent::Poll(ControllerPoll { adapter, buffer, time: start + offset }))?;
+        }
+        Ok(())
+    }
+}
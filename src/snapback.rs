@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::controller;
+use crate::duration_to_frame_count;
+
+//an axis has to be pushed this far before letting go counts as a release, same as the smash zones
+const HELD: i8 = 64;
+//crossing the middle by less than this is just noise
+const OVERSHOOT: i8 = 10;
+//the spring takes about a frame to get back to the middle, so give it a few
+const RELEASE_WINDOW: Duration = Duration::from_millis(70);
+const RECENT_LEN: usize = 5;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+/// The stick going past the middle after being let go of.
+#[derive(Clone, Copy, Debug)]
+pub struct Snapback {
+    pub axis: Axis,
+    /// Furthest it went on the wrong side, signed.
+    pub peak: i8,
+    /// Both axes at the peak.
+    pub peak_pos: (i8, i8),
+    pub duration: Duration,
+    pub time: Instant,
+}
+
+impl Snapback {
+    pub fn frames(&self) -> f64 {
+        duration_to_frame_count(self.duration)
+    }
+
+    /// Whether the game would have seen it, these are the ones that cause dashbacks.
+    pub fn leaves_deadzone(&self) -> bool {
+        self.peak.unsigned_abs() >= controller::DEADZONE as u8
+    }
+}
+
+#[derive(Clone, Copy)]
+enum AxisState {
+    Idle,
+    Held(i8),
+    Released { sign: i8, at: Instant },
+    Overshoot { sign: i8, start: Instant, peak: i8, peak_pos: (i8, i8) },
+}
+
+#[derive(Clone, Copy)]
+struct AxisTracker {
+    axis: Axis,
+    state: AxisState,
+}
+
+impl AxisTracker {
+    /// Returns true on a release, and the snapback once it's over.
+    fn update(&mut self, value: i8, pos: (i8, i8), time: Instant) -> (bool, Option<Snapback>) {
+        let sign = value.signum();
+        let mut released = false;
+        let mut snapback = None;
+        self.state = match self.state {
+            //going all the way over on purpose, like a dashback, isn't a snapback
+            _ if value.unsigned_abs() >= HELD as u8 => AxisState::Held(sign),
+            AxisState::Idle => AxisState::Idle,
+            AxisState::Held(held) => {
+                released = true;
+                AxisState::Released { sign: held, at: time }
+            }
+            AxisState::Released { sign: held, at } => {
+                if sign == -held && value.unsigned_abs() >= OVERSHOOT as u8 {
+                    AxisState::Overshoot { sign: held, start: time, peak: value, peak_pos: pos }
+                }
+                else if time.saturating_duration_since(at) > RELEASE_WINDOW {
+                    AxisState::Idle
+                }
+                else {
+                    self.state
+                }
+            }
+            AxisState::Overshoot { sign: held, start, peak, peak_pos } => {
+                if sign == -held && value.unsigned_abs() >= OVERSHOOT as u8 {
+                    if value.unsigned_abs() > peak.unsigned_abs() {
+                        AxisState::Overshoot { sign: held, start, peak: value, peak_pos: pos }
+                    }
+                    else {
+                        self.state
+                    }
+                }
+                else {
+                    snapback = Some(Snapback { axis: self.axis, peak, peak_pos, duration: time.saturating_duration_since(start), time: start });
+                    AxisState::Idle
+                }
+            }
+        };
+        (released, snapback)
+    }
+}
+
+pub struct SnapbackStats {
+    pub releases: u32,
+    pub snapbacks: u32,
+    pub out_of_deadzone: u32,
+    pub total_frames: f64,
+    pub worst: Option<Snapback>,
+    pub recent: VecDeque<Snapback>,
+}
+
+impl SnapbackStats {
+    pub fn average_frames(&self) -> f64 {
+        if self.snapbacks == 0 {
+            return 0.
+        }
+        self.total_frames / self.snapbacks as f64
+    }
+
+    /// Fraction of releases that snapped back far enough for the game to see it.
+    pub fn bad_release_rate(&self) -> f64 {
+        if self.releases == 0 {
+            return 0.
+        }
+        self.out_of_deadzone as f64 / self.releases as f64
+    }
+}
+
+/// Watches one controller's clamped stick positions for snapbacks.
+pub struct SnapbackDetector {
+    x: AxisTracker,
+    y: AxisTracker,
+    pub stats: SnapbackStats,
+}
+
+impl SnapbackDetector {
+    pub fn new() -> Self {
+        Self {
+            x: AxisTracker { axis: Axis::X, state: AxisState::Idle },
+            y: AxisTracker { axis: Axis::Y, state: AxisState::Idle },
+            stats: SnapbackStats { releases: 0, snapbacks: 0, out_of_deadzone: 0, total_frames: 0., worst: None, recent: VecDeque::new() },
+        }
+    }
+
+    pub fn update(&mut self, pos: (i8, i8), time: Instant) -> Vec<Snapback> {
+        let mut snapbacks = vec![];
+        for (tracker, value) in [(&mut self.x, pos.0), (&mut self.y, pos.1)] {
+            let (released, snapback) = tracker.update(value, pos, time);
+            if released {
+                self.stats.releases += 1;
+            }
+            snapbacks.extend(snapback);
+        }
+
+        for snapback in &snapbacks {
+            let stats = &mut self.stats;
+            stats.snapbacks += 1;
+            if snapback.leaves_deadzone() {
+                stats.out_of_deadzone += 1;
+            }
+            stats.total_frames += snapback.frames();
+            let worse = match stats.worst {
+                Some(worst) => snapback.peak.unsigned_abs() > worst.peak.unsigned_abs(),
+                None => true,
+            };
+            if worse {
+                stats.worst = Some(*snapback);
+            }
+            stats.recent.push_front(*snapback);
+            stats.recent.truncate(RECENT_LEN);
+        }
+        snapbacks
+    }
+}
+
+impl Default for SnapbackDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,7 +2,9 @@ use std::{collections::BTreeMap, time::{Duration, Instant}};
 
 use ggez::{Context, GameResult, graphics::{self, BlendMode, Canvas, Color, DrawMode, DrawParam, Drawable, Mesh, Rect, get_window_color_format}};
 
-use crate::{controller, reset_graphics, zones};
+use gcviewer::{controller, zones};
+
+use crate::window::reset_graphics;
 
 pub struct StickDisplay {
     pub plane: Box<dyn zones::Plane>,
@@ -70,6 +72,12 @@ impl StickDisplay {
 
     pub fn set_plane(&mut self, plane: Box<dyn zones::Plane>) {
         self.plane = plane;
+        self.redraw_background();
+    }
+
+    /// Starts drawing the background again from the left, a bit each frame.
+    pub fn redraw_background(&mut self) {
+        self.background_progress_x = -80;
         self.background_updated = false;
     }
 
@@ -119,6 +127,37 @@ impl StickDisplay {
         Ok(())
     }
 
+    /// Which stick coordinate is under a point on the window, if it's on the display.
+    pub fn stick_coords_at(&self, x: f32, y: f32) -> Option<(i8, i8)> {
+        let (x, y) = (x - self.x, y - self.y);
+        if x < 0. || y < 0. || x >= self.width || y >= self.height {
+            return None
+        }
+        let scale_x = self.width/220.;
+        let scale_y = self.height/220.;
+        //pixels are drawn right and down from their coordinate
+        let stick_x = ((x - self.width/2.) / scale_x).floor();
+        let stick_y = ((self.height/2. - y) / scale_y).ceil();
+        Some((stick_x.clamp(-128., 127.) as i8, stick_y.clamp(-128., 127.) as i8))
+    }
+
+    /// A closed line through points in stick coordinates, through the middle of their pixels.
+    pub fn draw_outline(&self, ctx: &mut Context, points: &[(f32, f32)], color: Color) -> GameResult<()> {
+        //ggez won't make a line that doesn't go anywhere, like a shape that's only been clicked so far
+        if points.len() < 2 || points.iter().all(|point| *point == points[0]) {
+            return Ok(())
+        }
+        let scale_x = self.width/220.;
+        let scale_y = self.height/220.;
+        let mut screen_points: Vec<[f32; 2]> = points.iter().map(|(x, y)| {
+            [self.x + self.width/2. + (x + 0.5) * scale_x, self.y + self.height/2. - (y - 0.5) * scale_y]
+        }).collect();
+        screen_points.push(screen_points[0]);
+        let line = Mesh::new_line(ctx, &screen_points, 2., color)?;
+        graphics::draw(ctx, &line, DrawParam::new())?;
+        Ok(())
+    }
+
     fn to_screen_coords(&self, coords: &(i8, i8)) -> [f32; 2] {
         let middlex = self.width/2.;
         let middley = self.height/2.;
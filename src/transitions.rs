@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::duration_to_frame_count;
+use crate::input_sequence::ControllerAction;
+use crate::zones::{Zone, ZoneTrait};
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Transition {
+    pub count: u32,
+    /// Time spent in the zone it came from, summed over every time it happened.
+    pub total_dwell: Duration,
+}
+
+impl Transition {
+    pub fn average_dwell(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO
+        }
+        self.total_dwell / self.count
+    }
+}
+
+/// Counts how the stick moves from zone to zone, from the `Enter`/`Leave`
+/// actions. Zones can overlap, so the stick is counted as in the first one
+/// in `zones` it's inside, and `None` is outside all of them.
+pub struct TransitionStats {
+    pub zones: Vec<Zone>,
+    inside: Vec<bool>,
+    state: Option<usize>,
+    since: Option<Instant>,
+    pub transitions: HashMap<(Option<usize>, Option<usize>), Transition>,
+}
+
+impl TransitionStats {
+    pub fn new(zones: Vec<Zone>) -> Self {
+        let inside = vec![false; zones.len()];
+        Self { zones, inside, state: None, since: None, transitions: HashMap::new() }
+    }
+
+    /// One poll's worth of actions, so a stick jumping through a zone
+    /// between polls doesn't count as being in it.
+    pub fn record(&mut self, actions: &[ControllerAction], time: Instant) {
+        for action in actions {
+            let (zone, entered) = match action {
+                ControllerAction::Enter(zone) => (zone, true),
+                ControllerAction::Leave(zone) => (zone, false),
+                _ => continue,
+            };
+            if let Some(index) = self.zones.iter().position(|z| z == zone) {
+                self.inside[index] = entered;
+            }
+        }
+
+        let state = self.inside.iter().position(|inside| *inside);
+        if state == self.state {
+            return
+        }
+        //the first change just says where the stick started
+        if let Some(since) = self.since {
+            let transition = self.transitions.entry((self.state, state)).or_default();
+            transition.count += 1;
+            transition.total_dwell += time.saturating_duration_since(since);
+        }
+        self.state = state;
+        self.since = Some(time);
+    }
+
+    pub fn zone_name(&self, zone: Option<usize>) -> &'static str {
+        match zone {
+            Some(index) => self.zones[index].get_name(),
+            None => "other",
+        }
+    }
+
+    /// Every state, outside all the zones last, for the rows and columns of the matrix.
+    pub fn states(&self) -> Vec<Option<usize>> {
+        (0..self.zones.len()).map(Some).chain(std::iter::once(None)).collect()
+    }
+
+    pub fn get(&self, from: Option<usize>, to: Option<usize>) -> Transition {
+        self.transitions.get(&(from, to)).copied().unwrap_or_default()
+    }
+
+    /// Chance the next zone is `to` given the stick is leaving `from`.
+    pub fn probability(&self, from: Option<usize>, to: Option<usize>) -> f64 {
+        let total: u32 = self.transitions.iter().filter(|((f, _), _)| *f == from).map(|(_, transition)| transition.count).sum();
+        if total == 0 {
+            return 0.
+        }
+        self.get(from, to).count as f64 / total as f64
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("from,to,count,probability,average_dwell_ms,average_dwell_frames\n");
+        for from in self.states() {
+            for to in self.states() {
+                let transition = self.get(from, to);
+                if transition.count == 0 {
+                    continue
+                }
+                let dwell = transition.average_dwell();
+                csv += &format!("{},{},{},{:.4},{:.3},{:.3}\n",
+                    self.zone_name(from), self.zone_name(to), transition.count, self.probability(from, to),
+                    dwell.as_secs_f64() * 1000., duration_to_frame_count(dwell));
+            }
+        }
+        csv
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::controller::{self, Controller, DEADZONE};
+use crate::duration_to_frame_count;
+use crate::zones::{self, TriggerZone};
+
+/*
+the game only reads the stick once a frame, so everything here works on the
+last poll before each frame instead of every poll.
+
+dashback: going from a dash one way to a smash the other way. vanilla turns it
+into a tilt turn if the stick spends any frame in the tilt zone on the way
+over, ucf lets one frame slide.
+
+shield drop (approximate): from shield, vanilla only drops through the platform
+in the thin band between y -.6625 and -.6875, anything lower with smash timing
+is a spot dodge. ucf drops anywhere in the down diagonals past -.6625.
+*/
+//.8, dash and smash turn threshold
+const SMASH_X: i8 = 64;
+//-.6625 and -.7
+const SHIELD_DROP_Y: i8 = -53;
+const SPOT_DODGE_Y: i8 = -56;
+//smash inputs have to get there within 2 frames of leaving the deadzone
+const SMASH_FRAMES: u32 = 2;
+//stop waiting for the other side of a dashback after this
+const DASHBACK_FRAMES: u32 = 8;
+const RECENT_LEN: usize = 6;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Outcome {
+    Dashback,
+    TiltTurn,
+    ShieldDrop,
+    SpotDodge,
+}
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Dashback => "dashback",
+            Outcome::TiltTurn => "tilt turn",
+            Outcome::ShieldDrop => "shield drop",
+            Outcome::SpotDodge => "spot dodge",
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self, Outcome::Dashback | Outcome::ShieldDrop)
+    }
+}
+
+/// What the same input would do on vanilla and with ucf.
+#[derive(Clone, Copy, Debug)]
+pub struct UcfResult {
+    pub vanilla: Outcome,
+    pub ucf: Outcome,
+    /// Frames spent in the tilt zone for dashbacks, or since leaving the deadzone for shield drops.
+    pub frames: u32,
+    /// The frame that decided it.
+    pub pos: (i8, i8),
+    pub time: Instant,
+}
+
+impl UcfResult {
+    pub fn differs(&self) -> bool {
+        self.vanilla != self.ucf
+    }
+}
+
+#[derive(Clone, Copy)]
+struct FrameSample {
+    pos: (i8, i8),
+    shielding: bool,
+    time: Instant,
+}
+
+#[derive(Clone, Copy)]
+enum DownState {
+    Up,
+    //frames since y left the deadzone
+    Down(u32),
+    //waiting for the stick to come back up before judging another one
+    Judged,
+}
+
+/// Runs a controller's clamped stick through vanilla's and ucf's dashback
+/// and shield drop rules.
+pub struct UcfAnalyzer {
+    start: Option<Instant>,
+    frame: u64,
+    //last poll of the frame in progress
+    pending: Option<FrameSample>,
+
+    //side of the last frame at or past the dash threshold, 0 if it's been too long
+    dash_side: i8,
+    tilt_frames: u32,
+    frames_since_dash: u32,
+
+    down: DownState,
+
+    pub recent: VecDeque<UcfResult>,
+}
+
+impl UcfAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            start: None,
+            frame: 0,
+            pending: None,
+            dash_side: 0,
+            tilt_frames: 0,
+            frames_since_dash: 0,
+            down: DownState::Up,
+            recent: VecDeque::new(),
+        }
+    }
+
+    pub fn update(&mut self, controller: &Controller, time: Instant) -> Vec<UcfResult> {
+        let start = *self.start.get_or_insert(time);
+        let frame = duration_to_frame_count(time.saturating_duration_since(start)) as u64;
+        let mut results = vec![];
+        if let Some(sample) = self.pending {
+            //a frame with no polls reads the same values again
+            for _ in self.frame..frame.min(self.frame + DASHBACK_FRAMES as u64) {
+                results.extend(self.frame_sample(sample));
+            }
+        }
+        let shield_zones: [TriggerZone; 2] = [zones::FULL_ANALOG, zones::DIGITAL_PRESS];
+        let shielding = shield_zones.iter().any(|zone| {
+            zone.in_zone(controller.l_analog(), controller.is_down(&controller::L_BUTTON))
+                || zone.in_zone(controller.r_analog(), controller.is_down(&controller::R_BUTTON))
+        });
+        self.frame = frame;
+        self.pending = Some(FrameSample { pos: controller.stick_clamp(), shielding, time });
+
+        for result in &results {
+            self.recent.push_front(*result);
+            self.recent.truncate(RECENT_LEN);
+        }
+        results
+    }
+
+    fn frame_sample(&mut self, sample: FrameSample) -> Option<UcfResult> {
+        let dashback = self.dashback(sample.pos, sample.time);
+        let shield_drop = self.shield_drop(sample);
+        dashback.or(shield_drop)
+    }
+
+    fn dashback(&mut self, pos: (i8, i8), time: Instant) -> Option<UcfResult> {
+        let x = pos.0;
+        let side = x.signum();
+        if x.unsigned_abs() >= SMASH_X as u8 {
+            let result = if self.dash_side == -side {
+                let vanilla = if self.tilt_frames == 0 { Outcome::Dashback } else { Outcome::TiltTurn };
+                let ucf = if self.tilt_frames <= 1 { Outcome::Dashback } else { Outcome::TiltTurn };
+                Some(UcfResult { vanilla, ucf, frames: self.tilt_frames, pos, time })
+            }
+            else {
+                None
+            };
+            self.dash_side = side;
+            self.tilt_frames = 0;
+            self.frames_since_dash = 0;
+            return result
+        }
+
+        if self.dash_side != 0 {
+            self.frames_since_dash += 1;
+            if side == self.dash_side {
+                //still on the dash side, whatever happened on the other side doesn't count
+                self.tilt_frames = 0;
+            }
+            else if side == -self.dash_side && x.unsigned_abs() >= DEADZONE as u8 {
+                self.tilt_frames += 1;
+            }
+            if self.frames_since_dash > DASHBACK_FRAMES {
+                self.dash_side = 0;
+                self.tilt_frames = 0;
+            }
+        }
+        None
+    }
+
+    fn shield_drop(&mut self, sample: FrameSample) -> Option<UcfResult> {
+        let (x, y) = sample.pos;
+        if y > -DEADZONE {
+            self.down = DownState::Up;
+            return None
+        }
+        let frames = match self.down {
+            DownState::Up => 1,
+            DownState::Down(frames) => frames + 1,
+            DownState::Judged => return None,
+        };
+        self.down = DownState::Down(frames);
+        if y > SHIELD_DROP_Y || !sample.shielding {
+            return None
+        }
+
+        let vanilla = if y > SPOT_DODGE_Y || frames > SMASH_FRAMES { Outcome::ShieldDrop } else { Outcome::SpotDodge };
+        let ucf = if x.unsigned_abs() >= DEADZONE as u8 { Outcome::ShieldDrop } else { vanilla };
+        self.down = DownState::Judged;
+        Some(UcfResult { vanilla, ucf, frames, pos: sample.pos, time: sample.time })
+    }
+}
+
+impl Default for UcfAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::time::{Duration, Instant};
+
+use rusb::{Device, GlobalContext};
+
+use crate::gc_adapter::{AdapterSender, is_still_connected};
+
+/*
+text usbmon lines look like this (https://www.kernel.org/doc/Documentation/usb/usbmon.txt)
+    ffff8c2a5f8f6f00 3911235614 C Ii:1:005:1 0:1 37 = 21140000 807f7f7f 1b1e0400 00000000 ...
+urb tag, timestamp in microseconds, event type, address (type+direction:bus:device:endpoint),
+status (status:interval for interrupt), length, then '=' and the data words in hex
+*/
+//the kernel only keeps the low 12 bits of the seconds
+const TIMESTAMP_WRAP: u64 = 4096 * 1_000_000;
+
+pub struct UsbmonTextEvent {
+    pub timestamp: u32,
+    pub event_type: char,
+    pub xfer_type: char,
+    pub is_in: bool,
+    pub bus: u16,
+    pub device: u8,
+    pub length: usize,
+    pub data: Vec<u8>,
+}
+
+pub fn parse_line(line: &str) -> Option<UsbmonTextEvent> {
+    let mut words = line.split_whitespace();
+    let _tag = words.next()?;
+    let timestamp = words.next()?.parse().ok()?;
+    let event_type = words.next()?.chars().next()?;
+
+    let mut address = words.next()?.split(':');
+    let mut kind = address.next()?.chars();
+    let xfer_type = kind.next()?;
+    let is_in = kind.next()? == 'i';
+    let bus = address.next()?.parse().ok()?;
+    let device = address.next()?.parse().ok()?;
+
+    //setup packets have a bunch more words, we don't care about those
+    let _status = words.next()?;
+    let length = words.next()?.parse().ok()?;
+
+    let mut data = Vec::new();
+    if words.next() == Some("=") {
+        for word in words {
+            for i in (0..word.len()).step_by(2) {
+                data.push(u8::from_str_radix(word.get(i..i + 2)?, 16).ok()?);
+            }
+        }
+    }
+
+    Some(UsbmonTextEvent { timestamp, event_type, xfer_type, is_in, bus, device, length, data })
+}
+
+/// Gets an adapter buffer out of an event if it's a completed interrupt read
+/// from the given device. The text interface only captures 32 bytes, so
+/// ports that got cut off are blanked out and show up as disconnected.
+pub fn poll_buffer(event: &UsbmonTextEvent, bus: u16, device: u8) -> Option<[u8; 37]> {
+    if event.bus != bus || event.device != device || event.event_type != 'C' || event.xfer_type != 'I' || !event.is_in {
+        return None
+    }
+    if event.length != 37 || event.data.first() != Some(&0x21) {
+        return None
+    }
+    let mut buffer = [0u8; 37];
+    let complete_ports = (event.data.len().min(37) - 1) / 9;
+    let len = 1 + complete_ports * 9;
+    buffer[..len].copy_from_slice(&event.data[..len]);
+    Some(buffer)
+}
+
+pub fn usbmon_text_path(bus: u8) -> String {
+    format!("/sys/kernel/debug/usb/usbmon/{}u", bus)
+}
+
+/// Like `try_snoop_usb`, but reads the text interface, which doesn't need the
+/// binary /dev/usbmon devices.
+pub fn snoop_usb_text(device: Device<GlobalContext>, sender: &mut AdapterSender) -> Result<(), Box<dyn std::error::Error>> {
+    let path = usbmon_text_path(device.bus_number());
+    let file = File::open(&path).map_err(|err| format!("couldn't open {}, is debugfs mounted and are we root? {}", path, err))?;
+    eprintln!("reading usbmon text from {}", path);
+
+    let mut first_event = None;
+    let mut last_timestamp = 0u32;
+    let mut wraps = 0u64;
+    let mut last_connected_check = Instant::now();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if let Some(event) = parse_line(&line) {
+            if event.timestamp < last_timestamp {
+                wraps += 1;
+            }
+            last_timestamp = event.timestamp;
+            let micros = wraps * TIMESTAMP_WRAP + event.timestamp as u64;
+            let (first_time, first_micros) = *first_event.get_or_insert((Instant::now(), micros));
+
+            if let Some(buffer) = poll_buffer(&event, device.bus_number() as u16, device.address()) {
+                let time = first_time + Duration::from_micros(micros - first_micros);
+                sender.send_poll(buffer, time)?;
+            }
+        }
+
+        if last_connected_check.elapsed() > Duration::from_secs(1) {
+            if !is_still_connected(&device) {
+                Err("gc adapter unplugged")?;
+            }
+            last_connected_check = Instant::now();
+        }
+    }
+    Err("usbmon text stream ended")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //P1 and P3 plugged in, P2 empty, P4 cut off after its first 4 bytes
+    const POLL: &str = "ffff8c2a5f8f6f00 3911235614 C Ii:1:005:1 0:1 37 = 21140000 807f7f7f 1b1e0400 00000000 00000014 0100837c 80802020 14000080";
+
+    #[test]
+    fn parses_poll() {
+        let event = parse_line(POLL).unwrap();
+        assert_eq!(event.timestamp, 3911235614);
+        assert_eq!((event.event_type, event.xfer_type, event.is_in), ('C', 'I', true));
+        assert_eq!((event.bus, event.device), (1, 5));
+        assert_eq!(event.length, 37);
+        assert_eq!(event.data.len(), 32);
+        assert_eq!(event.data[..10], [0x21, 0x14, 0x00, 0x00, 0x80, 0x7f, 0x7f, 0x7f, 0x1b, 0x1e]);
+    }
+
+    #[test]
+    fn cut_off_port_is_blanked() {
+        let event = parse_line(POLL).unwrap();
+        let buffer = poll_buffer(&event, 1, 5).unwrap();
+        assert_eq!(buffer[..28], event.data[..28]);
+        assert_eq!(buffer[28..], [0; 9]);
+    }
+
+    #[test]
+    fn only_polls_from_the_adapter() {
+        let event = parse_line(POLL).unwrap();
+        assert!(poll_buffer(&event, 2, 5).is_none());
+        assert!(poll_buffer(&event, 1, 6).is_none());
+
+        let other_bus = parse_line(&POLL.replace("Ii:1:005:1", "Ii:3:012:1")).unwrap();
+        assert_eq!((other_bus.bus, other_bus.device), (3, 12));
+        assert!(poll_buffer(&other_bus, 1, 5).is_none());
+        assert!(poll_buffer(&other_bus, 3, 12).is_some());
+    }
+
+    #[test]
+    fn ignores_submissions_and_setup() {
+        //the read being submitted, no data yet
+        let submit = parse_line("ffff8c2a5f8f6f00 3911235606 S Ii:1:005:1 -115:1 37 <").unwrap();
+        assert_eq!(submit.event_type, 'S');
+        assert!(submit.data.is_empty());
+        assert!(poll_buffer(&submit, 1, 5).is_none());
+
+        let setup = parse_line("ffff8c2a4d1b3c00 3911200012 S Co:1:005:0 s 21 0b 0000 0000 0000 0").unwrap();
+        assert_eq!((setup.event_type, setup.xfer_type, setup.is_in), ('S', 'C', false));
+        assert!(poll_buffer(&setup, 1, 5).is_none());
+
+        //rumble going out to the adapter
+        let rumble = parse_line("ffff8c2a5f8f6e40 3911235700 S Io:1:005:2 -115:1 5 = 11010000 00").unwrap();
+        assert!(!rumble.is_in);
+        assert_eq!(rumble.data, [0x11, 0x01, 0x00, 0x00, 0x00]);
+        assert!(poll_buffer(&rumble, 1, 5).is_none());
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(parse_line("").is_none());
+        assert!(parse_line("ffff8c2a5f8f6f00 notatime C Ii:1:005:1 0:1 37").is_none());
+        assert!(parse_line("ffff8c2a5f8f6f00 3911235614 C Ii:1:005:1 0:1 37 = 2114zz00").is_none());
+    }
+}
@@ -0,0 +1,1048 @@
+use std::convert::TryInto;
+use std::time::{Duration, Instant};
+
+use std::sync::mpsc::{Receiver, Sender};
+
+use std::collections::{HashMap, VecDeque};
+
+use ggez::input::keyboard::{KeyCode, KeyMods};
+use ggez::input::mouse::{self, MouseButton};
+use ggez::{Context, ContextBuilder, GameResult};
+use ggez::conf::WindowMode;
+use ggez::graphics::{self, Color, DrawMode, DrawParam, Rect, Text, TextFragment};
+use ggez::event::{self, EventHandler};
+
+use gcviewer::{analysis, calibration, controller, plane_config, ucf, zones};
+use gcviewer::calibration::{Calibrator, ProfileStore};
+use gcviewer::gate::GateMap;
+use gcviewer::history::InputHistory;
+use gcviewer::snapback::{SnapbackDetector, SnapbackStats};
+use gcviewer::transitions::TransitionStats;
+use gcviewer::ucf::{UcfAnalyzer, UcfResult};
+use gcviewer::zones::PlaneWithZones;
+use gcviewer::zone_library::ZoneLibrary;
+use gcviewer::controller::{Controller, StickStage};
+use gcviewer::controller::update_controllers;
+use gcviewer::duration_to_frame_count;
+
+use crate::oscilloscope::Oscilloscope;
+use crate::oscilloscope::ScopeDirection;
+
+use gcviewer::gc_adapter::AdapterEvent;
+use gcviewer::gc_adapter::RumbleRequest;
+
+use gcviewer::poll_stats::PollStats;
+use gcviewer::session::SessionWriter;
+
+use crate::stick_display::StickDisplay;
+
+use crate::button_display;
+
+use crate::zone_editor::ZoneEditor;
+
+use gcviewer::input_sequence::{ActionSuccess, InputSequence};
+use crate::oscilloscope::Scope;
+
+use crate::button_scope::ButtonScope;
+
+use gcviewer::analysis::{CompletedSequence, SequenceTracker};
+
+const WIDTH: u16 = 1600;
+const HEIGHT: u16 = 1000;
+
+//const ORANGE: Color = Color {255, 127, 0};
+const ORANGE: Color = Color { r: 1., g: 0.5, b: 0., a: 1. };
+
+/// Opens the window and runs it until it's closed.
+pub fn run(receiver: Receiver<AdapterEvent>, profiles: ProfileStore, sequence_tracker: SequenceTracker<'static>, rumble_sender: Option<Sender<RumbleRequest>>, session_writer: Option<SessionWriter>, history_length: Duration, libraries: Vec<ZoneLibrary>) {
+    let (mut ctx, event_loop) = ContextBuilder::new("gc viewer", "mister_person")
+        .window_mode(WindowMode::dimensions(Default::default(), WIDTH.into(), HEIGHT.into()))
+        .build()
+        .expect("aieee, could not create ggez context!");
+
+	let mut my_game = GameState::new(&mut ctx, receiver, profiles).unwrap();
+    my_game.sequence_tracker = sequence_tracker;
+    my_game.rumble_sender = rumble_sender;
+    my_game.session_writer = session_writer;
+    my_game.history.length = history_length;
+    my_game.libraries = libraries;
+    my_game.set_plane(0);
+
+	// Run!
+	event::run(ctx, event_loop, my_game);
+}
+
+//d-right cycles through these
+enum StickPosFormat {
+    //in stick units, as far along as that stage
+    Stage(StickStage),
+    Decimal,
+    //the float the game actually uses
+    Melee,
+}
+
+impl StickPosFormat {
+    fn name(&self) -> &'static str {
+        match self {
+            StickPosFormat::Stage(StickStage::Raw) => "raw",
+            StickPosFormat::Stage(StickStage::Origin) => "minus origin",
+            StickPosFormat::Stage(StickStage::Clamped) => "clamped",
+            StickPosFormat::Decimal => "clamped / 80",
+            StickPosFormat::Stage(StickStage::Deadzone) => "deadzone",
+            StickPosFormat::Melee => "melee",
+        }
+    }
+
+    fn next(&self) -> StickPosFormat {
+        match self {
+            StickPosFormat::Stage(StickStage::Raw) => StickPosFormat::Stage(StickStage::Origin),
+            StickPosFormat::Stage(StickStage::Origin) => StickPosFormat::Stage(StickStage::Clamped),
+            StickPosFormat::Stage(StickStage::Clamped) => StickPosFormat::Decimal,
+            StickPosFormat::Decimal => StickPosFormat::Stage(StickStage::Deadzone),
+            StickPosFormat::Stage(StickStage::Deadzone) => StickPosFormat::Melee,
+            StickPosFormat::Melee => StickPosFormat::Stage(StickStage::Raw),
+        }
+    }
+
+    fn stick_text(&self, controller: &Controller, c_stick: bool) -> String {
+        let at = |stage| if c_stick { controller.c_stick_at(stage) } else { controller.stick_at(stage) };
+        let (x, y) = match self {
+            StickPosFormat::Stage(stage) => {
+                let (x, y) = at(*stage);
+                (x as f64, y as f64)
+            }
+            StickPosFormat::Decimal => {
+                let (x, y) = at(StickStage::Clamped);
+                (x as f64 / 80., y as f64 / 80.)
+            }
+            StickPosFormat::Melee => {
+                let (x, y) = if c_stick { controller.c_stick_melee() } else { controller.stick_melee() };
+                //f32 to f64 adds junk digits, the steps are only 4 decimals anyway
+                ((x as f64 * 10000.).round() / 10000., (y as f64 * 10000.).round() / 10000.)
+            }
+        };
+        coords_text(x, y)
+    }
+
+    //the unclamped position gets shown as well when this is past clamping
+    fn is_clamped(&self) -> bool {
+        !matches!(self, StickPosFormat::Stage(StickStage::Raw | StickStage::Origin))
+    }
+}
+
+fn coords_text(x: f64, y: f64) -> String {
+    let mx = if x < 0.0 {'-'} else {' '};
+    let my = if y < 0.0 {'-'} else {' '};
+    format!("({}{:<6}, {}{:<6})", mx, x.abs(), my, y.abs())
+}
+
+struct GameState<'a> {
+    receiver: Receiver<AdapterEvent>,
+    //4 ports per adapter, adapter n has ports 4n..4n+4
+    controllers: Vec<Controller>,
+    current_controller: usize,
+
+    prev_coords: VecDeque<((i8, i8), Instant)>,
+    c_prev_coords: VecDeque<((i8, i8), Instant)>,
+
+    history: InputHistory,
+
+    paused: bool,
+    adapters_connected: Vec<bool>,
+    poll_stats: Vec<PollStats>,
+    //one per controller, like controllers
+    snapback_detectors: Vec<SnapbackDetector>,
+
+    session_writer: Option<SessionWriter>,
+
+    rumble_sender: Option<Sender<RumbleRequest>>,
+    //when to turn each adapter's motors off, by adapter id
+    rumble_until: HashMap<usize, Instant>,
+
+    profiles: ProfileStore,
+    calibrator: Option<Calibrator>,
+
+    //only while mapping the gate, G starts and stops it
+    gate_map: Option<GateMap>,
+    //vanilla vs ucf outcomes for the current controller, U turns it on and off
+    ucf: Option<UcfAnalyzer>,
+    //how the current controller's stick moves between the current plane's zones, T shows it and E saves it
+    transitions: TransitionStats,
+    show_transitions: bool,
+
+    stick_display: StickDisplay,
+    c_stick_display: StickDisplay,
+
+    scope_y: Oscilloscope,
+    scope_x: Oscilloscope,
+    c_scope_y: Oscilloscope,
+    c_scope_x: Oscilloscope,
+
+    button_scope: ButtonScope,
+
+    stick_pos_format: StickPosFormat,
+
+    //L cycles through the libraries and P through the planes in one, for the stick display and the stick scopes
+    libraries: Vec<ZoneLibrary>,
+    current_library: usize,
+    current_plane: usize,
+    //tab starts drawing a zone on the stick display
+    zone_editor: Option<ZoneEditor>,
+
+    sequence_tracker: SequenceTracker<'a>,
+    completed_sequences: Vec<CompletedSequence>,
+}
+
+impl<'a> GameState<'a> {
+    pub fn get_controller(&self) -> Controller {
+        self.controllers[self.current_controller]
+    }
+
+    /// None if that's from before the history starts.
+    pub fn get_inputs_at_time(&self, time: Instant) -> Option<[u8; 8]> {
+        self.history.state_at(self.current_controller, time)
+    }
+
+    pub fn planes(&self) -> &[PlaneWithZones] {
+        match self.libraries.get(self.current_library) {
+            Some(library) => &library.planes,
+            None => &[],
+        }
+    }
+
+    pub fn set_plane(&mut self, index: usize) {
+        let plane = match self.planes().get(index) {
+            Some(plane) => plane,
+            None => return,
+        };
+        let plane = plane.clone();
+        let c_stick = self.libraries[self.current_library].c_stick.clone();
+        self.current_plane = index;
+        self.stick_display.set_plane(Box::new(plane.clone()));
+        self.scope_x.plane = Box::new(plane.clone());
+        self.scope_y.plane = Box::new(plane);
+        self.c_stick_display.set_plane(Box::new(c_stick.clone()));
+        self.c_scope_x.plane = Box::new(c_stick.clone());
+        self.c_scope_y.plane = Box::new(c_stick);
+        self.update_used_zones();
+    }
+
+    /// Makes actions for every zone of the planes the sticks are drawn with, so
+    /// new ones can be used straight away. The transition counts start again
+    /// since the zones they were counted between might have gone.
+    fn update_used_zones(&mut self) {
+        let stick_zones = self.stick_display.plane.zones();
+        let c_stick_zones = self.c_stick_display.plane.zones();
+        self.transitions = TransitionStats::new(stick_zones.clone());
+        self.sequence_tracker.set_zones(stick_zones, c_stick_zones, zones::get_some_trigger_zones());
+    }
+
+    /// Puts the editor's zone back where it was in the current plane, or at the
+    /// top if it's new, and saves the planes file if that's where the plane came from.
+    fn save_edited_zone(&mut self) {
+        let editor = match &self.zone_editor {
+            Some(editor) => editor,
+            None => return,
+        };
+        let zone = match editor.zone() {
+            Some(zone) => zone,
+            None => {
+                println!("the zone needs a name and a shape before it can be saved");
+                return
+            }
+        };
+        let color = editor.zone_color();
+        let editing = editor.editing;
+        let plane = match self.libraries[self.current_library].planes.get_mut(self.current_plane) {
+            Some(plane) => plane,
+            None => return,
+        };
+        match editing {
+            Some(index) if index < plane.zones.len() => plane.zones[index] = (zone, color),
+            _ => plane.zones.insert(0, (zone, color)),
+        }
+        self.zone_editor = Some(ZoneEditor::new());
+        self.save_library();
+    }
+
+    fn delete_edited_zone(&mut self) {
+        let index = match self.zone_editor.as_ref().and_then(|editor| editor.editing) {
+            Some(index) => index,
+            None => return,
+        };
+        let plane = match self.libraries[self.current_library].planes.get_mut(self.current_plane) {
+            Some(plane) if index < plane.zones.len() => plane,
+            _ => return,
+        };
+        plane.zones.remove(index);
+        self.zone_editor = Some(ZoneEditor::new());
+        self.save_library();
+    }
+
+    /// Left and right go through the current plane's zones to change one, with a new zone before the first.
+    fn pick_zone(&mut self, forward: bool) {
+        let zones = match self.planes().get(self.current_plane) {
+            Some(plane) => plane.zones.clone(),
+            None => return,
+        };
+        let editor = match &mut self.zone_editor {
+            Some(editor) => editor,
+            None => return,
+        };
+        let count = zones.len() + 1;
+        let position = editor.editing.map_or(0, |index| index + 1);
+        let position = if forward { (position + 1) % count } else { (position + count - 1) % count };
+        *editor = match position.checked_sub(1) {
+            Some(index) => ZoneEditor::edit(index, &zones[index].0, &zones[index].1),
+            None => ZoneEditor::new(),
+        };
+    }
+
+    /// Writes the current library back to the planes file if it came from one,
+    /// otherwise the changes only last until gcviewer closes.
+    fn save_library(&mut self) {
+        let library = &mut self.libraries[self.current_library];
+        match library.path.clone() {
+            Some(path) => match plane_config::save_library(&path, library) {
+                Ok(()) => {
+                    library.unsaved = false;
+                    println!("saved zone planes to {}", path);
+                }
+                Err(err) => {
+                    library.unsaved = true;
+                    println!("couldn't save zone planes to {}: {}", path, err);
+                }
+            },
+            _ => library.unsaved = true,
+        }
+        //draws the background again with the changes
+        self.set_plane(self.current_plane);
+    }
+
+    fn warn_unsaved(&self) {
+        for library in self.libraries.iter().filter(|library| library.unsaved) {
+            eprintln!("zone changes to {} weren't saved", library.name);
+        }
+    }
+
+    /// ggez exits without dropping anything, so whatever's buffered has to go out first.
+    fn flush_recording(&mut self) {
+        if let Some(writer) = self.session_writer.as_mut() {
+            if let Err(err) = writer.flush() {
+                println!("couldn't write to session file: {}", err);
+            }
+        }
+    }
+
+    /// Buzz the current controller, does nothing unless started with --rumble.
+    pub fn buzz(&mut self, duration: Duration) {
+        if let Some(sender) = &self.rumble_sender {
+            let adapter = self.current_controller / 4;
+            let mut state = [false; 4];
+            state[self.current_controller % 4] = true;
+            let _ = sender.send((adapter, state));
+            self.rumble_until.insert(adapter, Instant::now() + duration);
+        }
+    }
+
+    fn add_adapter(&mut self, adapter: usize) {
+        if self.adapters_connected.len() <= adapter {
+            self.adapters_connected.resize(adapter + 1, false);
+            self.poll_stats.resize_with(adapter + 1, PollStats::new);
+            self.snapback_detectors.resize_with((adapter + 1) * 4, SnapbackDetector::new);
+            for index in self.controllers.len()..(adapter + 1) * 4 {
+                self.controllers.push(self.profiles.new_controller(index));
+            }
+        }
+    }
+
+    /// Saves the calibration under the controller's profile, or puts the old one back.
+    fn finish_calibration(&mut self, save: bool) {
+        let calibrator = match self.calibrator.take() {
+            Some(calibrator) => calibrator,
+            None => return,
+        };
+        let index = calibrator.controller;
+        if !save {
+            self.controllers[index].set_calibration(calibrator.previous);
+            return
+        }
+        self.controllers[index].set_calibration(calibrator.calibration());
+        let name = self.profiles.profile_name(index);
+        self.profiles.set(&name, calibrator.calibration());
+        match self.profiles.save() {
+            Ok(()) => println!("saved calibration profile {} to {}", name, self.profiles.path),
+            Err(err) => println!("couldn't save calibration profile to {}: {}", self.profiles.path, err),
+        }
+    }
+
+    pub fn new<'b>(ctx: &'b mut Context, receiver: Receiver<AdapterEvent>, profiles: ProfileStore) -> GameResult<GameState<'a>> {
+        let scope_y = Oscilloscope::new(ctx, 40., 0., 1000., 200., ScopeDirection::Horizontal)?;
+        let scope_x = Oscilloscope::new(ctx, 40., 200., 1000., 200., ScopeDirection::Horizontal)?;
+        let mut c_scope_y = Oscilloscope::new(ctx, 40., 400., 1000., 130., ScopeDirection::Horizontal)?;
+        let mut c_scope_x = Oscilloscope::new(ctx, 40., 530., 1000., 130., ScopeDirection::Horizontal)?;
+        c_scope_y.plane = Box::new(PlaneWithZones::c_stick_plane());
+        c_scope_x.plane = Box::new(PlaneWithZones::c_stick_plane());
+        let button_scope = ButtonScope::new(ctx, 40., 660., 1000., 180., ScopeDirection::Horizontal)?;
+        let stick_display = StickDisplay::new(ctx, 1200., 0., 440, 440)?;
+        let mut c_stick_display = StickDisplay::new(ctx, 1310., 400., 220, 220)?;
+        c_stick_display.set_plane(Box::new(PlaneWithZones::c_stick_plane()));
+        Ok(GameState {
+            receiver,
+            controllers: (0..4).map(|index| profiles.new_controller(index)).collect(),
+            current_controller: 0,
+            prev_coords: VecDeque::new(),
+            c_prev_coords: VecDeque::new(),
+            paused: false,
+            adapters_connected: vec![],
+            poll_stats: vec![],
+            snapback_detectors: (0..4).map(|_| SnapbackDetector::new()).collect(),
+            session_writer: None,
+            rumble_sender: None,
+            rumble_until: HashMap::new(),
+            profiles,
+            calibrator: None,
+            gate_map: None,
+            ucf: None,
+            transitions: TransitionStats::new(vec![]),
+            show_transitions: false,
+            scope_y,
+            scope_x,
+            c_scope_y,
+            c_scope_x,
+            stick_display,
+            c_stick_display,
+            stick_pos_format: StickPosFormat::Stage(StickStage::Clamped),
+            libraries: vec![],
+            current_library: 0,
+            current_plane: 0,
+            zone_editor: None,
+            button_scope,
+            history: InputHistory::new(Duration::from_secs_f64(crate::HISTORY_SECONDS)),
+            sequence_tracker: SequenceTracker::new(&[]),
+            completed_sequences: vec![],
+        })
+    }
+}
+
+impl<'a> EventHandler<ggez::GameError> for GameState<'a> {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        loop {
+            let poll = match self.receiver.try_recv() {
+                Ok(AdapterEvent::Poll(poll)) => poll,
+                Ok(AdapterEvent::Connected(adapter)) => {
+                    self.add_adapter(adapter);
+                    self.adapters_connected[adapter] = true;
+                    //might be a different adapter or overclock than last time
+                    self.poll_stats[adapter].reset();
+                    continue
+                }
+                Ok(AdapterEvent::Disconnected(adapter)) => {
+                    self.add_adapter(adapter);
+                    self.adapters_connected[adapter] = false;
+                    //the controllers might be replugged, so pick up their origins again
+                    for index in adapter * 4..adapter * 4 + 4 {
+                        self.controllers[index] = self.profiles.new_controller(index);
+                        self.snapback_detectors[index] = SnapbackDetector::new();
+                    }
+                    continue
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                //input source finished (end of a replay), keep showing the last inputs
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    self.adapters_connected.iter_mut().for_each(|connected| *connected = false);
+                    self.flush_recording();
+                    break
+                }
+            };
+            if let Some(writer) = self.session_writer.as_mut() {
+                if let Err(err) = writer.write_poll(&poll) {
+                    println!("couldn't write to session file, stopping recording: {}", err);
+                    self.session_writer = None;
+                }
+            }
+            self.add_adapter(poll.adapter);
+            self.poll_stats[poll.adapter].record(poll.time);
+            update_controllers(&mut self.controllers[poll.adapter * 4..poll.adapter * 4 + 4], &poll.buffer);
+            let ports = poll.adapter * 4..poll.adapter * 4 + 4;
+            for (controller, detector) in self.controllers[ports.clone()].iter().zip(&mut self.snapback_detectors[ports]) {
+                detector.update(controller.stick_clamp(), poll.time);
+            }
+            for index in poll.adapter * 4..poll.adapter * 4 + 4 {
+                self.history.record(index, poll.time, self.controllers[index].buffer);
+            }
+
+            //push the A button on a controller to switch to it
+            for (i, controller) in self.controllers.iter().enumerate() {
+                if controller.is_down(&controller::A_BUTTON) && i != self.current_controller {
+                    self.current_controller = i;
+                }
+            }
+
+            if self.get_controller().just_pressed(&controller::A_BUTTON) {
+                //self.update_background(ctx)?;
+            }
+            if self.get_controller().just_pressed(&controller::D_RIGHT_BUTTON) {
+                self.stick_pos_format = self.stick_pos_format.next();
+            }
+            //X+Y+Start recentres and starts calibrating, then A saves it and B puts the old one back
+            let mut calibration_done = None;
+            if let Some(calibrator) = self.calibrator.as_mut() {
+                let controller = &self.controllers[calibrator.controller];
+                calibrator.update(controller);
+                if controller.just_pressed(&controller::A_BUTTON) {
+                    calibration_done = Some(true);
+                }
+                else if controller.just_pressed(&controller::B_BUTTON) {
+                    calibration_done = Some(false);
+                }
+            }
+            if let Some(save) = calibration_done {
+                self.finish_calibration(save);
+            }
+            let calibrate_combo = calibration::is_calibrate_combo(&self.get_controller());
+            if calibrate_combo {
+                self.finish_calibration(false);
+                self.calibrator = Some(Calibrator::start(self.current_controller, &mut self.controllers[self.current_controller]));
+            }
+
+            //pause if start is pressed
+            if self.paused {
+                if self.get_controller().just_pressed(&controller::START_BUTTON) && !calibrate_combo {
+                    self.paused = false;
+                    self.scope_y.reset(ctx);
+                    self.scope_x.reset(ctx);
+                    self.c_scope_y.reset(ctx);
+                    self.c_scope_x.reset(ctx);
+                }
+                continue
+            }
+            else if self.get_controller().just_pressed(&controller::START_BUTTON) && !calibrate_combo {
+                self.paused = true;
+            }
+
+            let buttons = self.get_controller().buffer[0..2].try_into();
+            if let Ok(buttons) = buttons {
+                self.button_scope.update(ctx, buttons, poll.time)?;
+            }
+
+            let stick_pos = self.get_controller().stick_pos();
+            let clamp_pos = controller::clamp(stick_pos.0, stick_pos.1);
+            let c_stick_pos = self.get_controller().c_stick_pos();
+            let c_clamp_pos = controller::clamp(c_stick_pos.0, c_stick_pos.1);
+
+            let controller = self.get_controller();
+            let actions = self.sequence_tracker.actions(&controller);
+            self.transitions.record(&actions, poll.time);
+            let completed = self.sequence_tracker.judge(&actions, &controller, poll.time);
+            if let Some(ucf) = self.ucf.as_mut() {
+                ucf.update(&controller, poll.time);
+            }
+            let missed = completed.iter().any(analysis::has_miss);
+            self.completed_sequences.extend(completed);
+            if missed {
+                self.buzz(Duration::from_millis(150));
+            }
+
+            if let Some(gate_map) = self.gate_map.as_mut() {
+                gate_map.record(stick_pos);
+            }
+
+            //add trail points to stick display
+            self.prev_coords.push_front((stick_pos, poll.time));
+            self.stick_display.add_point(ctx, clamp_pos)?;
+            if clamp_pos != stick_pos {
+                self.stick_display.add_point(ctx, stick_pos)?;
+            }
+
+            //TODO correlate this size with oscilloscope trail size (maybe)
+            //remove old points from stick display
+            if self.prev_coords.len() > 500 {
+                let (old_stick_pos, _time) = self.prev_coords.pop_back().expect("I just checked len, it can't be empty");
+                let old_clamp_pos = controller::clamp(old_stick_pos.0, old_stick_pos.1);
+                self.stick_display.remove_point(ctx, old_stick_pos)?;
+                if old_clamp_pos != old_stick_pos {
+                    self.stick_display.remove_point(ctx, old_clamp_pos)?;
+                }
+            }
+
+            //ditto above but c stick, TODO dry
+            self.c_prev_coords.push_front((c_stick_pos, poll.time));
+            self.c_stick_display.add_point(ctx, c_clamp_pos)?;
+            if c_clamp_pos != c_stick_pos {
+                self.c_stick_display.add_point(ctx, c_stick_pos)?;
+            }
+
+            if self.c_prev_coords.len() > 500 {
+                let (old_c_stick_pos, _time) = self.c_prev_coords.pop_back().expect("I just checked len, it can't be empty");
+                let old_c_clamp_pos = controller::clamp(old_c_stick_pos.0, old_c_stick_pos.1);
+                self.c_stick_display.remove_point(ctx, old_c_stick_pos)?;
+                if old_c_clamp_pos != old_c_stick_pos {
+                    self.c_stick_display.remove_point(ctx, old_c_clamp_pos)?;
+                }
+            }
+
+            self.scope_x.update(ctx, (clamp_pos.0, clamp_pos), poll.time)?;
+            self.scope_y.update(ctx, (clamp_pos.1, clamp_pos), poll.time)?;
+            self.c_scope_x.update(ctx, (c_clamp_pos.0, c_clamp_pos), poll.time)?;
+            self.c_scope_y.update(ctx, (c_clamp_pos.1, c_clamp_pos), poll.time)?;
+
+        }
+
+        let now = Instant::now();
+        let rumble_sender = &self.rumble_sender;
+        self.rumble_until.retain(|adapter, until| {
+            if now < *until {
+                return true
+            }
+            if let Some(sender) = rumble_sender {
+                let _ = sender.send((*adapter, [false; 4]));
+            }
+            false
+        });
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, Color::BLACK);
+
+        self.stick_display.draw(ctx)?;
+
+        if self.get_controller().stick_clamp() != self.get_controller().stick_pos() {
+            self.stick_display.draw_point(ctx, self.get_controller().stick_pos(), Color::RED)?;
+        }
+        self.stick_display.draw_point(ctx, self.get_controller().stick_clamp(), Color::WHITE)?;
+
+        self.c_stick_display.draw(ctx)?;
+
+        self.button_scope.draw(ctx)?;
+
+        button_display::draw_buttons(ctx, &self.get_controller(), 1050., 660., button_display::LINE_LAYOUT)?;
+
+        self.scope_y.draw(ctx)?;
+        self.scope_x.draw(ctx)?;
+        self.c_scope_y.draw(ctx)?;
+        self.c_scope_x.draw(ctx)?;
+
+        let mouse_pos = mouse::position(ctx);
+        let mut instant = None;
+        instant = instant.or_else(|| self.scope_y.get_time_from_pos(mouse_pos.x, mouse_pos.y));
+        instant = instant.or_else(|| self.scope_x.get_time_from_pos(mouse_pos.x, mouse_pos.y));
+        instant = instant.or_else(|| self.c_scope_y.get_time_from_pos(mouse_pos.x, mouse_pos.y));
+        instant = instant.or_else(|| self.c_scope_x.get_time_from_pos(mouse_pos.x, mouse_pos.y));
+        instant = instant.or_else(|| self.button_scope.get_time_from_pos(mouse_pos.x, mouse_pos.y));
+        if let Some(instant) = instant {
+            let text = self.scope_y.scope_start_time.saturating_duration_since(instant).as_millis().to_string();
+            draw_text(ctx, text, 200., 0., Color::WHITE)?;
+            self.scope_y.draw_line_at_time(ctx, instant)?;
+            self.scope_x.draw_line_at_time(ctx, instant)?;
+            self.c_scope_y.draw_line_at_time(ctx, instant)?;
+            self.c_scope_x.draw_line_at_time(ctx, instant)?;
+            self.button_scope.draw_line_at_time(ctx, instant)?;
+
+            if let Some(inputs) = self.get_inputs_at_time(instant) {
+                let mut controller = self.get_controller().clone();
+                controller.from_buffer(&inputs);
+                let point = controller.stick_clamp();
+                for x in -1..=1 {
+                    for y in -1..=1 {
+                        let color = if x == 0 && y == 0 {Color::BLACK} else {Color::WHITE};
+                        self.stick_display.draw_point(ctx, (point.0 + x, point.1 + y), color)?;
+                    }
+                }
+            }
+        }
+
+        let controller = self.get_controller();
+        let format = &self.stick_pos_format;
+        let unclamped_text = |(x, y): (i8, i8)| coords_text(x as f64, y as f64);
+        draw_text(ctx, format.stick_text(&controller, false), 0., 0., Color::WHITE)?;
+        if format.is_clamped() && controller.stick_pos() != controller.stick_clamp() {
+            draw_text(ctx, unclamped_text(controller.stick_pos()), 0., 15., Color::RED)?;
+        }
+
+        let (raw_x, raw_y) = controller.stick_raw();
+        draw_text(ctx, format!("({:<5}, {:<5})", raw_x, raw_y), 0., 30., Color::BLUE)?;
+
+        draw_text(ctx, format.stick_text(&controller, true), 400., 400., Color::from_rgb(0xff, 0xff, 0x00))?;
+        if format.is_clamped() && controller.c_stick_pos() != controller.c_stick_clamp() {
+            draw_text(ctx, unclamped_text(controller.c_stick_pos()), 400., 415., Color::from_rgb(0xc0, 0xc0, 0x00))?;
+        }
+
+        draw_text(ctx, format!("(fpx: {})", ggez::timer::fps(ctx)), 250., 0., Color::WHITE)?;
+        if !self.adapters_connected.contains(&true) {
+            draw_text(ctx, "adapter disconnected", 450., 0., Color::RED)?;
+        }
+
+        if let Some(calibrator) = &self.calibrator {
+            let calibration = calibrator.calibration();
+            let range = |range: calibration::StickRange| format!("x {}..{} y {}..{}", range.min.0, range.max.0, range.min.1, range.max.1);
+            draw_text(ctx, format!("calibrating P{}: sweep both sticks round the rim and press the triggers in, A saves, B cancels", calibrator.controller + 1), 450., 15., Color::YELLOW)?;
+            let text = format!("stick {}, c stick {}, L max {}, R max {}", range(calibration.stick_range), range(calibration.c_stick_range), calibration.l_max, calibration.r_max);
+            draw_text(ctx, text, 450., 30., Color::YELLOW)?;
+        }
+
+        draw_text(ctx, format!("L: {}", self.get_controller().l_analog()), 0., 45., Color::WHITE)?;
+        draw_text(ctx, format!("R: {}", self.get_controller().r_analog()), 0., 60., Color::WHITE)?;
+        draw_text(ctx, format!("stick values: {}", self.stick_pos_format.name()), 0., 75., Color::from_rgb(128, 128, 128))?;
+        if let Some(plane) = self.planes().get(self.current_plane) {
+            let library = &self.libraries[self.current_library];
+            draw_text(ctx, format!("zones: {}, {} (P / L to change)", library.name, plane.name), 0., 90., Color::from_rgb(128, 128, 128))?;
+            if library.unsaved {
+                let text = match library.path {
+                    Some(_) => "zone changes not saved, the planes file couldn't be written",
+                    None => "zone changes not saved, start with --planes <file> to keep them",
+                };
+                draw_text(ctx, text, 0., 105., ORANGE)?;
+            }
+        }
+
+        for (i, controller) in self.controllers.iter().enumerate() {
+            let status = controller.status();
+            let mut text = format!("P{}", i + 1);
+            if status.controller_type == controller::ControllerType::Wireless {
+                text += " wavebird";
+            }
+            let color = match status.is_connected() {
+                false => Color::from_rgb(0x50, 0x50, 0x50),
+                true if i == self.current_controller => Color::YELLOW,
+                true => Color::WHITE,
+            };
+            //one column per adapter
+            draw_text(ctx, text, 1060. + (i / 4) as f32 * 100., 450. + (i % 4) as f32 * 15., color)?;
+        }
+
+        let mut y_pos = 700.;
+        for sequence in self.completed_sequences.iter().rev().take(4) {
+            let num_lines = draw_completed_sequence(ctx, sequence, 1100., y_pos)?;
+            y_pos += (num_lines + 1) as f32 * 15.;
+        }
+
+        if let Some(stats) = self.poll_stats.get(self.current_controller / 4) {
+            draw_poll_stats(ctx, stats, 1050., 10.)?;
+        }
+
+        draw_snapback_stats(ctx, &self.snapback_detectors[self.current_controller].stats, 1050., 530.)?;
+
+        if let Some(ucf) = &self.ucf {
+            draw_text(ctx, "vanilla vs ucf, U to hide", 650., 200., Color::WHITE)?;
+            for (i, result) in ucf.recent.iter().enumerate() {
+                let color = match (result.vanilla.is_success(), result.ucf.is_success()) {
+                    (true, true) => Color::GREEN,
+                    (false, false) => Color::RED,
+                    _ => ORANGE,
+                };
+                draw_text(ctx, ucf_text(result), 650., 215. + i as f32 * 15., color)?;
+            }
+        }
+
+        if let Some(gate_map) = &self.gate_map {
+            self.draw_gate_map(ctx, gate_map, 650., 0.)?;
+        }
+
+        if let Some(editor) = &self.zone_editor {
+            let fg_color = editor.zone_color().fg_color;
+            let text = match editor.editing {
+                Some(_) => format!("changing zone \"{}\", {} (left/right zone, tab shape, up/down colour, enter saves, delete removes, esc stops)", editor.name, editor.color_name()),
+                None => format!("new {:?} zone \"{}\", {} (left/right zone, tab shape, up/down colour, enter saves, esc stops)", editor.shape, editor.name, editor.color_name()),
+            };
+            draw_text(ctx, text, 450., 45., fg_color.into())?;
+            draw_text(ctx, "drag on the stick display, polygons are a click per corner, right click undoes", 450., 60., Color::WHITE)?;
+            self.stick_display.draw_outline(ctx, &editor.outline(), fg_color.into())?;
+        }
+
+        if self.show_transitions {
+            draw_transitions(ctx, &self.transitions, 40., 845.)?;
+        }
+
+        graphics::present(ctx)
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, _keymods: KeyMods, _repeat: bool) {
+        //typing a name shouldn't set anything else off
+        if let Some(editor) = self.zone_editor.as_mut() {
+            match keycode {
+                KeyCode::Escape => self.zone_editor = None,
+                KeyCode::Tab => editor.next_shape(),
+                KeyCode::Up => editor.next_color(false),
+                KeyCode::Down => editor.next_color(true),
+                KeyCode::Left => self.pick_zone(false),
+                KeyCode::Right => self.pick_zone(true),
+                KeyCode::Delete => self.delete_edited_zone(),
+                KeyCode::Back => {
+                    editor.name.pop();
+                }
+                KeyCode::Return => self.save_edited_zone(),
+                _ => {}
+            }
+            return
+        }
+        match keycode {
+            KeyCode::Tab => self.zone_editor = Some(ZoneEditor::new()),
+            KeyCode::Escape => {
+                self.flush_recording();
+                self.warn_unsaved();
+                event::quit(ctx);
+            }
+            KeyCode::G => {
+                match self.gate_map.take() {
+                    Some(gate_map) => print_notches(&gate_map),
+                    None => {
+                        //start from what's already in the trail
+                        let mut gate_map = GateMap::new();
+                        for (point, count) in &self.stick_display.prev_coords_counter {
+                            if *count > 0 {
+                                gate_map.record(*point);
+                            }
+                        }
+                        self.gate_map = Some(gate_map);
+                    }
+                }
+            }
+            KeyCode::P if !self.planes().is_empty() => self.set_plane((self.current_plane + 1) % self.planes().len()),
+            KeyCode::L if !self.libraries.is_empty() => {
+                self.current_library = (self.current_library + 1) % self.libraries.len();
+                self.set_plane(0);
+            }
+            KeyCode::T => self.show_transitions = !self.show_transitions,
+            KeyCode::E => {
+                let path = "zone_transitions.csv";
+                match std::fs::write(path, self.transitions.to_csv()) {
+                    Ok(_) => println!("saved zone transitions to {}", path),
+                    Err(err) => println!("couldn't save zone transitions to {}: {}", path, err),
+                }
+            }
+            KeyCode::U => {
+                self.ucf = match self.ucf {
+                    Some(_) => None,
+                    None => Some(UcfAnalyzer::new()),
+                };
+            }
+            KeyCode::S => {
+                let adapter = self.current_controller / 4;
+                if let Some(stats) = self.poll_stats.get(adapter) {
+                    let path = format!("poll_stats_adapter{}.csv", adapter);
+                    match std::fs::write(&path, stats.to_csv()) {
+                        Ok(_) => println!("saved poll stats to {}", path),
+                        Err(err) => println!("couldn't save poll stats to {}: {}", path, err),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) {
+        if let Some(editor) = self.zone_editor.as_mut() {
+            //these would break the planes file
+            if !character.is_control() && !matches!(character, ',' | ':' | '#' | '"') {
+                editor.name.push(character);
+            }
+        }
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        let pos = self.stick_display.stick_coords_at(x, y);
+        if let (Some(editor), Some(pos)) = (self.zone_editor.as_mut(), pos) {
+            match button {
+                MouseButton::Left => editor.mouse_down(pos),
+                MouseButton::Right => editor.undo(),
+                _ => {}
+            }
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, _button: MouseButton, _x: f32, _y: f32) {
+        if let Some(editor) = self.zone_editor.as_mut() {
+            editor.mouse_up();
+        }
+    }
+
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        let pos = self.stick_display.stick_coords_at(x, y);
+        if let (Some(editor), Some(pos)) = (self.zone_editor.as_mut(), pos) {
+            editor.mouse_move(pos);
+        }
+    }
+
+    fn quit_event(&mut self, _ctx: &mut Context) -> bool {
+        self.flush_recording();
+        self.warn_unsaved();
+        false
+    }
+}
+
+impl<'a> GameState<'a> {
+    fn draw_gate_map(&self, ctx: &mut Context, gate_map: &GateMap, x: f32, y: f32) -> GameResult<()> {
+        for point in gate_map.outline() {
+            self.stick_display.draw_point(ctx, point, Color::from_rgb(128, 128, 128))?;
+        }
+        draw_text(ctx, format!("mapping gate, spin the stick round the rim, G to stop ({:.0}% traced)", gate_map.coverage() * 100.), x, y, Color::WHITE)?;
+        for (i, notch) in gate_map.notches().iter().enumerate() {
+            let color = match notch.target_offset() {
+                Some((0, 0)) => Color::GREEN,
+                Some(_) => ORANGE,
+                None => Color::MAGENTA,
+            };
+            self.stick_display.draw_point(ctx, controller::clamp(notch.pos.0, notch.pos.1), color)?;
+            draw_text(ctx, notch_text(notch), x, y + (15 * (i + 1)) as f32, color)?;
+        }
+        Ok(())
+    }
+}
+
+fn notch_text(notch: &gcviewer::gate::Notch) -> String {
+    let (x, y) = controller::clamp(notch.pos.0, notch.pos.1);
+    let (melee_x, melee_y) = notch.melee();
+    let mut text = format!("{:5.1} deg ({}, {}) = ({:.4}, {:.4})", notch.angle, x, y, melee_x, melee_y);
+    if let (Some(target), Some(offset)) = (notch.target, notch.target_offset()) {
+        text += &format!(" {} {:+} {:+}", target.name, offset.0, offset.1);
+    }
+    text
+}
+
+fn print_notches(gate_map: &GateMap) {
+    println!("gate {:.0}% traced, notches:", gate_map.coverage() * 100.);
+    for notch in gate_map.notches() {
+        println!("    {}", notch_text(&notch));
+    }
+}
+
+fn ucf_text(result: &UcfResult) -> String {
+    let frames = match result.vanilla {
+        ucf::Outcome::Dashback | ucf::Outcome::TiltTurn => format!("{} frames in tilt", result.frames),
+        _ => format!("{} frames from deadzone", result.frames),
+    };
+    format!("vanilla {}, ucf {} ({}, at {}, {})", result.vanilla.name(), result.ucf.name(), frames, result.pos.0, result.pos.1)
+}
+
+//rows are the zone the stick left, columns where it went, each cell is how many times and the chance of it
+fn draw_transitions(ctx: &mut Context, stats: &TransitionStats, x: f32, y: f32) -> GameResult<()> {
+    const COLUMN_WIDTH: f32 = 105.;
+    let states = stats.states();
+    for (i, to) in states.iter().enumerate() {
+        draw_text(ctx, stats.zone_name(*to), x + COLUMN_WIDTH * (i + 1) as f32, y, Color::CYAN)?;
+    }
+    for (row, from) in states.iter().enumerate() {
+        let y = y + 15. * (row + 1) as f32;
+        draw_text(ctx, stats.zone_name(*from), x, y, Color::CYAN)?;
+        for (column, to) in states.iter().enumerate() {
+            let transition = stats.get(*from, *to);
+            if transition.count == 0 {
+                continue
+            }
+            let probability = stats.probability(*from, *to);
+            let color = if probability >= 0.5 { Color::YELLOW } else { Color::WHITE };
+            let text = format!("{} ({:.0}%) {:.1}f", transition.count, probability * 100., duration_to_frame_count(transition.average_dwell()));
+            draw_text(ctx, text, x + COLUMN_WIDTH * (column + 1) as f32, y, color)?;
+        }
+    }
+    Ok(())
+}
+
+fn draw_snapback_stats(ctx: &mut Context, stats: &SnapbackStats, x: f32, y: f32) -> GameResult<()> {
+    draw_text(ctx, format!("snapbacks {} / {} releases", stats.snapbacks, stats.releases), x, y, Color::WHITE)?;
+    let color = if stats.out_of_deadzone > 0 { ORANGE } else { Color::WHITE };
+    draw_text(ctx, format!("out of deadzone {} ({:.1}%)", stats.out_of_deadzone, stats.bad_release_rate() * 100.), x, y + 15., color)?;
+    draw_text(ctx, format!("average {:.2} frames", stats.average_frames()), x, y + 30., Color::WHITE)?;
+    if let Some(worst) = stats.worst {
+        draw_text(ctx, format!("worst {:?} {:+} ({}, {}) {:.2} frames", worst.axis, worst.peak, worst.peak_pos.0, worst.peak_pos.1, worst.frames()), x, y + 45., color)?;
+    }
+    for (i, snapback) in stats.recent.iter().enumerate() {
+        let color = if snapback.leaves_deadzone() { ORANGE } else { Color::from_rgb(128, 128, 128) };
+        draw_text(ctx, format!("{:?} {:+} {:.2} frames", snapback.axis, snapback.peak, snapback.frames()), x, y + (60 + 15 * i) as f32, color)?;
+    }
+    Ok(())
+}
+
+fn draw_poll_stats(ctx: &mut Context, stats: &PollStats, x: f32, y: f32) -> GameResult<()> {
+    draw_text(ctx, format!("{:.1} hz", stats.rate()), x, y, Color::WHITE)?;
+    draw_text(ctx, format!("median {:.3} ms", stats.median_interval().as_secs_f64() * 1000.), x, y + 15., Color::WHITE)?;
+    draw_text(ctx, format!("worst {:.3} ms", stats.worst_gap.as_secs_f64() * 1000.), x, y + 30., Color::WHITE)?;
+    draw_text(ctx, format!("dropped {}", stats.dropped), x, y + 45., if stats.dropped > 0 { ORANGE } else { Color::WHITE })?;
+    draw_text(ctx, format!("duplicated {}", stats.duplicated), x, y + 60., if stats.duplicated > 0 { ORANGE } else { Color::WHITE })?;
+
+    //histogram of poll intervals, 0.5ms per bar, last bar is 10ms and up
+    const BAR_WIDTH: f32 = 7.;
+    const MAX_HEIGHT: f32 = 80.;
+    let bottom = y + 80. + MAX_HEIGHT;
+    let max = *stats.histogram.iter().max().unwrap_or(&0);
+    if max > 0 {
+        for (i, count) in stats.histogram.iter().enumerate() {
+            let height = *count as f32 / max as f32 * MAX_HEIGHT;
+            let rect = [x + i as f32 * BAR_WIDTH, bottom - height, BAR_WIDTH - 1., height].into();
+            let bar = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), rect, Color::CYAN)?;
+            graphics::draw(ctx, &bar, DrawParam::new())?;
+        }
+    }
+    draw_text(ctx, "0", x, bottom + 2., Color::WHITE)?;
+    draw_text(ctx, "10ms", x + 10. * 2. * BAR_WIDTH - 10., bottom + 2., Color::WHITE)?;
+    Ok(())
+}
+
+//returns line count
+fn draw_completed_sequence(ctx: &mut Context, sequence: &CompletedSequence, x: f32, y: f32) -> GameResult<i32> {
+    let (name, actions, success_rate) = sequence;
+    let name_text = format!("{}, ", name);
+    let chance_text = format!("{}%", success_rate * 100.);
+    let chance_color = match *success_rate {
+        x if x == 1. => Color::GREEN,
+        x if x == 0. => Color::RED,
+        x if x > 0. && x < 1. => ORANGE,
+        _ => Color::MAGENTA,
+    };
+    draw_colored_text(ctx, vec![name_text, "chance of success: ".to_string(), chance_text], x, y, &[Color::YELLOW, Color::CYAN, chance_color])?;
+
+    let dot_start_y = y + (15*actions.len() + 15) as f32;
+    let mut dot_x_pos = x;
+    const FRAME_LENGTH: f32 = 30.;
+    for i in 0..15 {
+        let x = x + i as f32 * FRAME_LENGTH;
+        let points = [[x, dot_start_y + 3.], [x, dot_start_y + 12.]];
+        let line = graphics::Mesh::new_line(ctx, &points, 1., Color::from_rgb(128, 128, 128))?;
+        graphics::draw(ctx, &line, DrawParam::new())?;
+    }
+
+    for (i, (input, since_last, success)) in actions.iter().enumerate() {
+        let color = match success {
+            ActionSuccess::EarlyMiss => Color::from_rgb(255, 0, 128),
+            ActionSuccess::Early => Color::MAGENTA,
+            ActionSuccess::Success => Color::CYAN,
+            ActionSuccess::Late => ORANGE,
+            ActionSuccess::LateMiss => Color::RED,
+        };
+        let text = format!("{}, time: {:.3} frames ({} ms) {}", input.to_string(), duration_to_frame_count(*since_last), since_last.as_millis(), success);
+        draw_text(ctx, text, x, y + (15*(i + 1)) as f32, color)?;
+
+        dot_x_pos += duration_to_frame_count(*since_last) as f32 * FRAME_LENGTH;
+        let dot = graphics::Mesh::new_circle(ctx, DrawMode::fill(), [dot_x_pos, dot_start_y + 7.], 3., 1., color)?;
+        graphics::draw(ctx, &dot, DrawParam::new())?;
+    }
+    Ok((actions.len() + 2).try_into().unwrap())
+}
+
+fn draw_colored_text<F>(ctx: &mut Context, texts: Vec<F>, x: f32, y: f32, colors: &[Color]) -> GameResult<()>
+where F: Into<TextFragment>
+{
+    let mut x = x;
+    for (text_str, color) in texts.into_iter().zip(colors.iter()) {
+        let text = Text::new(TextFragment::new(text_str).color(*color));
+        graphics::draw(ctx, &text, DrawParam::new().dest([x, y]))?;
+        x += text.width(ctx);
+    }
+    Ok(())
+}
+
+fn draw_text<F>(ctx: &mut Context, text: F, x: f32, y: f32, color: Color) -> GameResult<()>
+where F: Into<TextFragment>
+{
+    let coords_text = TextFragment::new(text).color(color);
+    graphics::draw(ctx, &Text::new(coords_text), DrawParam::new().dest([x, y]))?;
+    Ok(())
+}
+
+pub fn reset_graphics(ctx: &mut Context) -> GameResult<()> {
+    graphics::set_canvas(ctx, None);
+    graphics::set_screen_coordinates(ctx, Rect::new(0., 0., WIDTH as f32, HEIGHT as f32))?;
+    Ok(())
+}
@@ -0,0 +1,256 @@
+use gcviewer::zones::{Polygon, SquareZone, Wedge, Zone, ZoneColor, ZoneTrait};
+
+//name, background, trail
+type Swatch = (&'static str, (u8, u8, u8), (u8, u8, u8));
+
+//up and down go through these
+pub const PALETTE: [Swatch; 7] = [
+    ("green", (0x00, 0x60, 0x00), (0x00, 0xff, 0x00)),
+    ("blue", (0x00, 0x00, 0x60), (0x00, 0x00, 0xff)),
+    ("red", (0x60, 0x00, 0x00), (0xff, 0x00, 0x00)),
+    ("purple", (0x40, 0x00, 0x40), (0x80, 0x00, 0xff)),
+    ("orange", (0x60, 0x30, 0x00), (0xff, 0x80, 0x00)),
+    ("cyan", (0x00, 0x40, 0x40), (0x00, 0xff, 0xff)),
+    ("yellow", (0x50, 0x50, 0x00), (0xff, 0xff, 0x00)),
+];
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum EditShape {
+    Rectangle,
+    //dragged from one corner to the other, anticlockwise
+    Wedge,
+    Polygon,
+}
+
+/// A zone being drawn on the stick display with the mouse, or one of the
+/// plane's zones being changed.
+pub struct ZoneEditor {
+    pub shape: EditShape,
+    //the two corners of a rectangle or wedge, or every corner of a polygon
+    pub points: Vec<(i8, i8)>,
+    dragging: bool,
+    pub name: String,
+    pub color: usize,
+    //where the zone being changed is in the plane, none for a new one
+    pub editing: Option<usize>,
+    //the zone being changed as it was, it keeps its shape until a new one is drawn
+    //and its colour until up or down picks another
+    original: Option<(Zone, ZoneColor)>,
+    keep_color: bool,
+}
+
+fn angle_tenths(pos: (i8, i8)) -> u16 {
+    ((pos.1 as f64).atan2(pos.0 as f64).to_degrees() * 10.).rem_euclid(3600.).round() as u16 % 3600
+}
+
+fn magnitude(pos: (i8, i8)) -> f64 {
+    ((pos.0 as f64).powi(2) + (pos.1 as f64).powi(2)).sqrt()
+}
+
+fn rectangle_outline(a: (f32, f32), b: (f32, f32)) -> Vec<(f32, f32)> {
+    vec![a, (b.0, a.1), b, (a.0, b.1)]
+}
+
+//angles in degrees, anticlockwise from start to end
+fn wedge_outline(start: f32, mut end: f32, inner: f32, outer: f32) -> Vec<(f32, f32)> {
+    if end < start {
+        end += 360.;
+    }
+    //a point every couple of degrees is smooth enough for the arcs
+    let steps = ((end - start) / 2.).ceil().max(1.) as usize;
+    let arc = |radius: f32| (0..=steps).map(move |i| {
+        let angle = (start + (end - start) * i as f32 / steps as f32).to_radians();
+        (radius * angle.cos(), radius * angle.sin())
+    });
+    arc(outer).chain(arc(inner).collect::<Vec<_>>().into_iter().rev()).collect()
+}
+
+impl ZoneEditor {
+    pub fn new() -> Self {
+        Self {
+            shape: EditShape::Rectangle, points: vec![], dragging: false, name: String::new(), color: 0,
+            editing: None, original: None, keep_color: false,
+        }
+    }
+
+    /// Starts changing the plane's zone at `index`. Circles and combined zones
+    /// can be renamed, recoloured, deleted or drawn again, but not reshaped bit by bit.
+    pub fn edit(index: usize, zone: &Zone, color: &ZoneColor) -> Self {
+        let mut editor = Self::new();
+        editor.editing = Some(index);
+        editor.name = zone.get_name().to_string();
+        editor.shape = match zone {
+            Zone::Wedge(_) => EditShape::Wedge,
+            Zone::Polygon(_) => EditShape::Polygon,
+            _ => EditShape::Rectangle,
+        };
+        match PALETTE.iter().position(|(_, bg_color, fg_color)| (*bg_color, *fg_color) == (color.bg_color, color.fg_color)) {
+            Some(swatch) => editor.color = swatch,
+            None => editor.keep_color = true,
+        }
+        editor.original = Some((zone.clone(), color.clone()));
+        editor
+    }
+
+    pub fn next_shape(&mut self) {
+        self.shape = match self.shape {
+            EditShape::Rectangle => EditShape::Wedge,
+            EditShape::Wedge => EditShape::Polygon,
+            EditShape::Polygon => EditShape::Rectangle,
+        };
+        self.points.clear();
+    }
+
+    pub fn mouse_down(&mut self, pos: (i8, i8)) {
+        match self.shape {
+            EditShape::Rectangle | EditShape::Wedge => {
+                self.points = vec![pos, pos];
+                self.dragging = true;
+            }
+            EditShape::Polygon => self.points.push(pos),
+        }
+    }
+
+    pub fn mouse_move(&mut self, pos: (i8, i8)) {
+        if self.dragging {
+            self.points[1] = pos;
+        }
+    }
+
+    pub fn mouse_up(&mut self) {
+        self.dragging = false;
+    }
+
+    /// Right click, takes off the last polygon corner or starts the shape again.
+    pub fn undo(&mut self) {
+        match self.shape {
+            EditShape::Polygon => {
+                self.points.pop();
+            }
+            _ => self.points.clear(),
+        }
+    }
+
+    pub fn next_color(&mut self, forward: bool) {
+        let step = if forward { 1 } else { PALETTE.len() - 1 };
+        //going from a zone's own colour starts at the palette's first
+        if !self.keep_color {
+            self.color = (self.color + step) % PALETTE.len();
+        }
+        self.keep_color = false;
+    }
+
+    pub fn color_name(&self) -> &str {
+        match self.keep_color {
+            true => "its own colour",
+            false => PALETTE[self.color].0,
+        }
+    }
+
+    pub fn zone_color(&self) -> ZoneColor {
+        let (bg_color, fg_color) = match &self.original {
+            Some((_, color)) if self.keep_color => (color.bg_color, color.fg_color),
+            _ => (PALETTE[self.color].1, PALETTE[self.color].2),
+        };
+        //the legend keeps its name unless the zone was renamed
+        let name = match &self.original {
+            Some((zone, color)) if zone.get_name() == self.name.trim() => color.name.clone(),
+            _ => self.name.trim().to_string(),
+        };
+        ZoneColor { name, bg_color, fg_color }
+    }
+
+    /// None until it has a name and enough corners.
+    pub fn zone(&self) -> Option<Zone> {
+        if self.name.trim().is_empty() {
+            return None
+        }
+        //zone names are static, see plane_config
+        let name: &'static str = match &self.original {
+            Some((zone, _)) if zone.get_name() == self.name.trim() => zone.get_name(),
+            _ => Box::leak(self.name.trim().to_string().into_boxed_str()),
+        };
+        if let (Some((zone, _)), true) = (&self.original, self.points.is_empty()) {
+            let mut zone = zone.clone();
+            zone.set_name(name);
+            return Some(zone)
+        }
+        match (self.shape, self.points.as_slice()) {
+            (EditShape::Rectangle, [a, b]) => Some(Zone::SquareZone(SquareZone {
+                min_x: a.0.min(b.0), max_x: a.0.max(b.0), min_y: a.1.min(b.1), max_y: a.1.max(b.1), name
+            })),
+            (EditShape::Wedge, [a, b]) if a != b => Some(Zone::Wedge(Wedge {
+                min_angle: angle_tenths(*a),
+                max_angle: angle_tenths(*b),
+                min_magnitude: magnitude(*a).min(magnitude(*b)).round() as u8,
+                max_magnitude: magnitude(*a).max(magnitude(*b)).round() as u8,
+                name,
+            })),
+            (EditShape::Polygon, points) if points.len() >= 3 => Some(Zone::Polygon(Polygon {
+                points: Box::leak(points.to_vec().into_boxed_slice()), name
+            })),
+            _ => None,
+        }
+    }
+
+    /// The shape so far in stick coordinates, to draw as a closed line. Empty
+    /// until it covers more than one point, a click without a drag doesn't yet.
+    pub fn outline(&self) -> Vec<(f32, f32)> {
+        let outline = match (self.shape, self.points.as_slice(), &self.original) {
+            (_, [], Some((zone, _))) => match zone {
+                Zone::SquareZone(square) => rectangle_outline(
+                    (square.min_x as f32, square.min_y as f32), (square.max_x as f32, square.max_y as f32)),
+                //the outer edge of an open ended wedge is drawn at the rim
+                Zone::Wedge(wedge) => wedge_outline(wedge.min_angle as f32 / 10., wedge.max_angle as f32 / 10.,
+                    wedge.min_magnitude as f32, (wedge.max_magnitude as f32).min(127.)),
+                Zone::Circle(circle) => wedge_outline(0., 360., 0., circle.radius as f32).into_iter()
+                    .map(|(x, y)| (x + circle.center.0 as f32, y + circle.center.1 as f32)).collect(),
+                Zone::Polygon(polygon) => polygon.points.iter().map(|(x, y)| (*x as f32, *y as f32)).collect(),
+                _ => vec![],
+            },
+            (EditShape::Rectangle, [a, b], _) => rectangle_outline((a.0 as f32, a.1 as f32), (b.0 as f32, b.1 as f32)),
+            (EditShape::Wedge, [a, b], _) => wedge_outline(angle_tenths(*a) as f32 / 10., angle_tenths(*b) as f32 / 10.,
+                magnitude(*a).min(magnitude(*b)) as f32, magnitude(*a).max(magnitude(*b)) as f32),
+            (_, points, _) => points.iter().map(|(x, y)| (*x as f32, *y as f32)).collect(),
+        };
+        match outline.iter().all(|point| *point == outline[0]) {
+            true => vec![],
+            false => outline,
+        }
+    }
+}
+
+impl Default for ZoneEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn click_without_drag_has_no_outline() {
+        for shape in [EditShape::Rectangle, EditShape::Wedge, EditShape::Polygon] {
+            let mut editor = ZoneEditor::new();
+            editor.shape = shape;
+            editor.mouse_down((30, 40));
+            assert!(editor.outline().is_empty(), "{:?}", shape);
+        }
+        //the middle has no angle, so a wedge from there is all one point too
+        let mut editor = ZoneEditor::new();
+        editor.shape = EditShape::Wedge;
+        editor.mouse_down((0, 0));
+        assert!(editor.outline().is_empty());
+    }
+
+    #[test]
+    fn dragged_rectangle_has_corners() {
+        let mut editor = ZoneEditor::new();
+        editor.mouse_down((-10, -20));
+        editor.mouse_move((30, 40));
+        editor.mouse_up();
+        assert_eq!(editor.outline(), vec![(-10., -20.), (30., -20.), (30., 40.), (-10., 40.)]);
+    }
+}
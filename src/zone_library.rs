@@ -0,0 +1,171 @@
+use crate::zones::{self, Circle, PlaneWithZones, SquareZone, Wedge, Zone, ZoneColor};
+
+/*
+planes for specific characters and techniques, in stick units after clamping
+(.0125 each, so 23 is .2875 and 80 is 1). the deadzone is anything under 23 on
+an axis, and the game only cares about what's outside it.
+*/
+
+/// A set of planes that go together, like everything one character drills.
+pub struct ZoneLibrary {
+    pub name: String,
+    pub planes: Vec<PlaneWithZones>,
+    //the c stick doesn't change with P
+    pub c_stick: PlaneWithZones,
+    //the planes file it came from, changes get saved back there
+    pub path: Option<String>,
+    //zones were changed since it was loaded or saved
+    pub unsaved: bool,
+}
+
+impl ZoneLibrary {
+    pub fn new(name: &str, planes: Vec<PlaneWithZones>) -> Self {
+        Self { name: name.to_string(), planes, c_stick: PlaneWithZones::c_stick_plane(), path: None, unsaved: false }
+    }
+}
+
+fn color(name: &str, bg_color: (u8, u8, u8), fg_color: (u8, u8, u8)) -> ZoneColor {
+    ZoneColor { name: name.to_string(), bg_color, fg_color }
+}
+
+const fn square(min_x: i8, max_x: i8, min_y: i8, max_y: i8, name: &'static str) -> Zone {
+    Zone::SquareZone(SquareZone { min_x, max_x, min_y, max_y, name })
+}
+
+//angles in tenths of a degree, same as Wedge
+const fn wedge(min_angle: u16, max_angle: u16, name: &'static str) -> Zone {
+    Zone::Wedge(Wedge { min_angle, max_angle, min_magnitude: 0, max_magnitude: 255, name })
+}
+
+const DEADZONE_COLOR: ((u8, u8, u8), (u8, u8, u8)) = ((0x40, 0x40, 0x40), (0xc0, 0xc0, 0xc0));
+
+fn with_deadzone(name: &str) -> PlaneWithZones {
+    let mut plane = PlaneWithZones::new(name);
+    plane.add_zone(Zone::SquareZone(zones::DEADZONE), color("deadzone", DEADZONE_COLOR.0, DEADZONE_COLOR.1));
+    plane
+}
+
+/*
+firefox/firebird go wherever the stick points once it's out of the deadzone,
+but an axis still inside the deadzone reads as 0. so the shallowest angled
+up b is .9500 .2875 (76, 23), about 16.9 degrees, anything flatter goes
+straight sideways, and the same the other way for the steepest.
+*/
+pub fn firefox() -> PlaneWithZones {
+    let mut plane = with_deadzone("firefox angles");
+    let straight = color("straight", (0x00, 0x00, 0x60), (0x00, 0x00, 0xff));
+    plane.add_zone(square(23, 127, -22, 22, "firefox right"), straight.clone());
+    plane.add_zone(square(-128, -23, -22, 22, "firefox left"), straight.clone());
+    plane.add_zone(square(-22, 22, 23, 127, "firefox up"), straight.clone());
+    plane.add_zone(square(-22, 22, -128, -23, "firefox down"), straight);
+    //within a couple of degrees of the shallowest and steepest angles
+    let extreme = color("shallowest / steepest", (0x60, 0x30, 0x00), (0xff, 0x80, 0x00));
+    for (min_angle, max_angle, name) in [
+        (168, 190, "firefox shallow up right"), (1610, 1632, "firefox shallow up left"),
+        (1968, 1990, "firefox shallow down left"), (3410, 3432, "firefox shallow down right"),
+        (710, 732, "firefox steep up right"), (1068, 1090, "firefox steep up left"),
+        (2510, 2532, "firefox steep down left"), (2868, 2890, "firefox steep down right"),
+    ] {
+        plane.add_zone(wedge(min_angle, max_angle, name), extreme.clone());
+    }
+    plane.add_zone(wedge(450, 450, "firefox diagonal"), color("diagonal", (0x40, 0x00, 0x40), (0x80, 0x00, 0xff)));
+    plane.add_zone(Zone::SquareZone(zones::EVERYTHING), color("angled", (0x00, 0x40, 0x00), (0x00, 0xff, 0x00)));
+    plane
+}
+
+/*
+waveland and ledgedash length only depends on how close to horizontal the air
+dodge is. it has to be below -.2875 to go into the ground at all, so the
+longest ones are y -23 with x as far out as it goes.
+*/
+pub fn waveland() -> PlaneWithZones {
+    let mut plane = with_deadzone("waveland / ledgedash");
+    let longest = color("longest", (0x00, 0x50, 0x00), (0x00, 0xff, 0x00));
+    let long = color("long", (0x00, 0x40, 0x40), (0x00, 0xff, 0xff));
+    let short = color("short", (0x40, 0x30, 0x00), (0xff, 0xc0, 0x00));
+    let air = color("stays in the air", (0x30, 0x00, 0x00), (0xff, 0x00, 0x00));
+    plane.add_zone(square(-128, 127, -22, 127, "air dodge"), air);
+    //down -.2875 to -.3625 and at least .8 out
+    plane.add_zone(square(64, 127, -29, -23, "waveland right longest"), longest.clone());
+    plane.add_zone(square(-128, -64, -29, -23, "waveland left longest"), longest);
+    plane.add_zone(square(23, 127, -56, -23, "waveland right"), long.clone());
+    plane.add_zone(square(-128, -23, -56, -23, "waveland left"), long);
+    plane.add_zone(square(-128, 127, -128, -23, "waveland short"), short);
+    plane
+}
+
+/*
+shine turnarounds (and sheik's down b) turn around as soon as x is past the
+deadzone the other way, so anything in the tilt zone works and it doesn't need
+to be a smash. straight down is safe either way.
+*/
+pub fn shine_turnaround() -> PlaneWithZones {
+    let mut plane = with_deadzone("shine turnaround");
+    plane.add_zone(square(-22, 22, -128, 127, "no turn"), color("no turn", (0x30, 0x30, 0x30), (0xa0, 0xa0, 0xa0)));
+    plane.add_zone(square(23, 63, -128, 127, "turn right tilt"), color("turn right", (0x00, 0x00, 0x60), (0x00, 0x00, 0xff)));
+    plane.add_zone(square(-63, -23, -128, 127, "turn left tilt"), color("turn left", (0x60, 0x00, 0x00), (0xff, 0x00, 0x00)));
+    //a smash turn still works, but it's easy to dash out of the shine instead
+    plane.add_zone(square(64, 127, -128, 127, "turn right smash"), color("turn right, smash", (0x00, 0x00, 0x30), (0x80, 0x80, 0xff)));
+    plane.add_zone(square(-128, -64, -128, 127, "turn left smash"), color("turn left, smash", (0x30, 0x00, 0x00), (0xff, 0x80, 0x80)));
+    plane
+}
+
+/*
+dolphin slash goes straight up unless x is out of the deadzone on the first
+frames, then it angles that way (and turns marth round if it's behind him).
+*/
+pub fn marth_up_b() -> PlaneWithZones {
+    let mut plane = with_deadzone("marth up b");
+    plane.add_zone(square(-22, 22, -128, 127, "up b straight"), color("straight up", (0x30, 0x30, 0x30), (0xa0, 0xa0, 0xa0)));
+    plane.add_zone(square(23, 127, -128, 127, "up b right"), color("angled right", (0x00, 0x00, 0x60), (0x00, 0x00, 0xff)));
+    plane.add_zone(square(-128, -23, -128, 127, "up b left"), color("angled left", (0x60, 0x00, 0x00), (0xff, 0x00, 0x00)));
+    plane
+}
+
+/// Where the stick sits for up tilts, up smashes and jumps, for checking
+/// the up b doesn't come out as something else.
+pub fn up_inputs() -> PlaneWithZones {
+    let mut plane = with_deadzone("up inputs");
+    plane.add_zone(Zone::SquareZone(zones::UP_SMASH), color("up smash / jump", (0x40, 0x00, 0x40), (0x80, 0x00, 0xff)));
+    plane.add_zone(square(-128, 127, 23, 52, "up tilt"), color("up tilt", (0x00, 0x60, 0x00), (0x00, 0xff, 0x00)));
+    plane.add_zone(Zone::Circle(Circle { center: (0, 0), radius: 127, name: "not up" }), color("not up", (0x30, 0x30, 0x30), (0xa0, 0xa0, 0xa0)));
+    plane
+}
+
+/// Everything bundled, L switches between them at runtime.
+pub fn character_libraries() -> Vec<ZoneLibrary> {
+    vec![
+        ZoneLibrary::new("fox / falco", vec![firefox(), shine_turnaround(), waveland()]),
+        ZoneLibrary::new("sheik", vec![shine_turnaround(), waveland(), up_inputs()]),
+        ZoneLibrary::new("marth", vec![marth_up_b(), up_inputs(), waveland()]),
+        ZoneLibrary::new("everyone", vec![waveland(), up_inputs()]),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zones::{Plane, ZoneTrait};
+
+    #[test]
+    fn deadzone_ends_at_23() {
+        for pos in [(22, 22), (-22, -22), (22, -22), (0, 0)] {
+            assert!(zones::DEADZONE.in_zone(pos), "{:?}", pos);
+        }
+        for pos in [(23, 0), (-23, 0), (0, 23), (0, -23)] {
+            assert!(!zones::DEADZONE.in_zone(pos), "{:?}", pos);
+        }
+    }
+
+    #[test]
+    fn planes_start_right_outside_the_deadzone() {
+        let firefox = firefox();
+        assert_eq!(firefox.get_zone((22, 0)).name, "deadzone");
+        assert_eq!(firefox.get_zone((23, 0)).name, "straight");
+        assert_eq!(firefox.get_zone((-23, 0)).name, "straight");
+        let waveland = waveland();
+        assert_eq!(waveland.get_zone((0, -22)).name, "deadzone");
+        assert_eq!(waveland.get_zone((0, -23)).name, "short");
+        assert_eq!(waveland.get_zone((0, 23)).name, "stays in the air");
+    }
+}
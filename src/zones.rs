@@ -1,10 +1,11 @@
-use crate::controller::clamp;
 
 /*
 cstick smashes use the same thresholds as control stick smashes (+/- .8000 X and +/- .6625 Y) while aerials happen as soon as you leave the deadzone (.2875)
 -altimor
 */
 
+use crate::controller;
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct ZoneColor {
     pub name: String,
@@ -20,29 +21,87 @@ pub trait ZoneTrait {
 #[derive(Debug, PartialEq, Hash, Eq, Clone)]
 pub enum Zone {
     SquareZone(SquareZone),
+    Wedge(Wedge),
+    Circle(Circle),
+    Polygon(Polygon),
+    Union(ZoneSet),
+    Intersection(ZoneSet),
+    Difference(Difference),
+}
+
+impl Zone {
+    pub fn union(name: &'static str, zones: Vec<Zone>) -> Zone {
+        Zone::Union(ZoneSet { zones, name })
+    }
+
+    pub fn intersection(name: &'static str, zones: Vec<Zone>) -> Zone {
+        Zone::Intersection(ZoneSet { zones, name })
+    }
+
+    pub fn difference(name: &'static str, zone: Zone, minus: Zone) -> Zone {
+        Zone::Difference(Difference { zone: Box::new(zone), minus: Box::new(minus), name })
+    }
+
+    /// Only renames this zone, the parts of a combined zone keep their own names.
+    pub fn set_name(&mut self, name: &'static str) {
+        match self {
+            Zone::SquareZone(zone) => zone.name = name,
+            Zone::Wedge(zone) => zone.name = name,
+            Zone::Circle(zone) => zone.name = name,
+            Zone::Polygon(zone) => zone.name = name,
+            Zone::Union(set) | Zone::Intersection(set) => set.name = name,
+            Zone::Difference(difference) => difference.name = name,
+        }
+    }
 }
 
 impl ZoneTrait for Zone {
     fn in_zone(&self, pos: (i8, i8)) -> bool {
         match self {
             Zone::SquareZone(zone) => zone.in_zone(pos),
+            Zone::Wedge(zone) => zone.in_zone(pos),
+            Zone::Circle(zone) => zone.in_zone(pos),
+            Zone::Polygon(zone) => zone.in_zone(pos),
+            Zone::Union(set) => set.zones.iter().any(|zone| zone.in_zone(pos)),
+            Zone::Intersection(set) => set.zones.iter().all(|zone| zone.in_zone(pos)),
+            Zone::Difference(difference) => difference.zone.in_zone(pos) && !difference.minus.in_zone(pos),
         }
     }
 
     fn get_name(&self) -> &'static str {
         match self {
             Zone::SquareZone(zone) => zone.get_name(),
+            Zone::Wedge(zone) => zone.get_name(),
+            Zone::Circle(zone) => zone.get_name(),
+            Zone::Polygon(zone) => zone.get_name(),
+            Zone::Union(set) | Zone::Intersection(set) => set.name,
+            Zone::Difference(difference) => difference.name,
         }
     }
 }
 
+/// The zones a union or intersection is made of.
+#[derive(Debug, PartialEq, Hash, Eq, Clone)]
+pub struct ZoneSet {
+    pub zones: Vec<Zone>,
+    pub name: &'static str,
+}
+
+/// In `zone` but not in `minus`.
+#[derive(Debug, PartialEq, Hash, Eq, Clone)]
+pub struct Difference {
+    pub zone: Box<Zone>,
+    pub minus: Box<Zone>,
+    pub name: &'static str,
+}
+
 #[derive(Debug, PartialEq, Hash, Eq, Clone)]
 pub struct SquareZone {
-    min_x: i8,
-    max_x: i8,
-    min_y: i8,
-    max_y: i8,
-    name: &'static str,
+    pub min_x: i8,
+    pub max_x: i8,
+    pub min_y: i8,
+    pub max_y: i8,
+    pub name: &'static str,
 }
 
 impl ZoneTrait for SquareZone {
@@ -55,8 +114,98 @@ impl ZoneTrait for SquareZone {
     }
 }
 
+fn magnitude_squared(pos: (i8, i8)) -> i32 {
+    (pos.0 as i32).pow(2) + (pos.1 as i32).pow(2)
+}
+
+/// Angles are in tenths of a degree going anticlockwise from straight right, so
+/// it can still be hashed. If min is more than max the wedge goes through 0.
+/// The middle has no angle, so it's never in a wedge.
+#[derive(Debug, PartialEq, Hash, Eq, Clone)]
+pub struct Wedge {
+    pub min_angle: u16,
+    pub max_angle: u16,
+    pub min_magnitude: u8,
+    pub max_magnitude: u8,
+    pub name: &'static str,
+}
+
+impl ZoneTrait for Wedge {
+    fn in_zone(&self, pos: (i8, i8)) -> bool {
+        let magnitude = magnitude_squared(pos);
+        if pos == (0, 0) || magnitude < (self.min_magnitude as i32).pow(2) || magnitude > (self.max_magnitude as i32).pow(2) {
+            return false
+        }
+        let angle = ((pos.1 as f64).atan2(pos.0 as f64).to_degrees() * 10.).rem_euclid(3600.).round() as u16 % 3600;
+        if self.min_angle <= self.max_angle {
+            angle >= self.min_angle && angle <= self.max_angle
+        }
+        else {
+            angle >= self.min_angle || angle <= self.max_angle
+        }
+    }
+
+    fn get_name(&self) -> &'static str {
+        self.name
+    }
+}
+
+#[derive(Debug, PartialEq, Hash, Eq, Clone)]
+pub struct Circle {
+    pub center: (i8, i8),
+    pub radius: u8,
+    pub name: &'static str,
+}
+
+impl ZoneTrait for Circle {
+    fn in_zone(&self, pos: (i8, i8)) -> bool {
+        let offset = (pos.0 as i32 - self.center.0 as i32, pos.1 as i32 - self.center.1 as i32);
+        offset.0.pow(2) + offset.1.pow(2) <= (self.radius as i32).pow(2)
+    }
+
+    fn get_name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Any shape, the corners go round in order. Points on the edges count as inside.
+#[derive(Debug, PartialEq, Hash, Eq, Clone)]
+pub struct Polygon {
+    pub points: &'static [(i8, i8)],
+    pub name: &'static str,
+}
+
+impl ZoneTrait for Polygon {
+    fn in_zone(&self, pos: (i8, i8)) -> bool {
+        let (x, y) = (pos.0 as i32, pos.1 as i32);
+        let mut inside = false;
+        for (i, a) in self.points.iter().enumerate() {
+            let b = self.points[(i + 1) % self.points.len()];
+            let (ax, ay, bx, by) = (a.0 as i32, a.1 as i32, b.0 as i32, b.1 as i32);
+            //on the edge
+            let cross = (bx - ax) * (y - ay) - (by - ay) * (x - ax);
+            if cross == 0 && x >= ax.min(bx) && x <= ax.max(bx) && y >= ay.min(by) && y <= ay.max(by) {
+                return true
+            }
+            //even-odd, count the edges a line going right from the point crosses
+            if (ay > y) != (by > y) {
+                let cross_x = ax as f64 + (y - ay) as f64 * (bx - ax) as f64 / (by - ay) as f64;
+                if (x as f64) < cross_x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
+    fn get_name(&self) -> &'static str {
+        self.name
+    }
+}
+
+//controller::DEADZONE is the first value out of it
 pub const DEADZONE: SquareZone = SquareZone {
-    min_x: -23, max_x: 23, min_y: -23, max_y: 23, name: "deadzone"
+    min_x: 1 - controller::DEADZONE, max_x: controller::DEADZONE - 1, min_y: 1 - controller::DEADZONE, max_y: controller::DEADZONE - 1, name: "deadzone"
 };
 pub const EVERYTHING: SquareZone = SquareZone {
     min_x: -128, max_x: 127, min_y: -128, max_y: 127, name: "everything"
@@ -74,133 +223,174 @@ pub const DOWN_SMASH: SquareZone = SquareZone {
     min_x: -128, max_x: 127, min_y: -128, max_y: -53, name: "down smash"
 };
 
-pub fn get_some_zones<'a>() -> Vec<&'a Zone> {
-    vec![
-        &Zone::SquareZone(DEADZONE),
-        &Zone::SquareZone(LEFT_SMASH),
-        &Zone::SquareZone(RIGHT_SMASH),
-        &Zone::SquareZone(UP_SMASH),
-        &Zone::SquareZone(DOWN_SMASH),
-    ]
-}
+//.9 to 1, the angle only counts once the stick is out this far
+pub const RIM: Wedge = Wedge {
+    min_angle: 0, max_angle: 3599, min_magnitude: 72, max_magnitude: 127, name: "rim"
+};
+//as shallow as a wavedash goes while still being down, the rest of the down diagonals are shorter
+pub const WAVEDASH_RIGHT: Wedge = Wedge {
+    min_angle: 3400, max_angle: 3599, min_magnitude: 72, max_magnitude: 127, name: "wavedash right"
+};
+pub const WAVEDASH_LEFT: Wedge = Wedge {
+    min_angle: 1801, max_angle: 2000, min_magnitude: 72, max_magnitude: 127, name: "wavedash left"
+};
+pub const CENTER: Circle = Circle {
+    center: (0, 0), radius: 8, name: "center"
+};
+//between the shallowest and steepest up right firefox angles
+pub const FIREFOX_UP_RIGHT: Polygon = Polygon {
+    points: &[(23, 23), (76, 23), (56, 56), (23, 76)], name: "firefox up right"
+};
 
-pub trait Plane {
-    fn get_zone(&self, point: (i8, i8)) -> ZoneColor;
-    fn get_name(&self) -> String;
+/// Either side, for when it doesn't matter which way it was smashed.
+pub fn f_smash() -> Zone {
+    Zone::union("f smash", vec![Zone::SquareZone(LEFT_SMASH), Zone::SquareZone(RIGHT_SMASH)])
 }
 
-pub struct PlaneWithZones<'a> {
-    zones: Vec<(&'a dyn ZoneTrait, ZoneColor)>,
+/// The rim, minus the parts that would be a smash.
+pub fn rim_no_smash() -> Zone {
+    Zone::difference("rim no smash", Zone::Wedge(RIM), Zone::union("smash", vec![
+        f_smash(),
+        Zone::SquareZone(UP_SMASH),
+        Zone::SquareZone(DOWN_SMASH),
+    ]))
 }
 
-impl<'a> PlaneWithZones<'a> {
-    pub fn new() -> Self {
-        Self { zones: Vec::new() }
-    }
+/// A range of analog trigger values, optionally only counting while the trigger is clicked in.
+#[derive(Debug, PartialEq, Hash, Eq, Clone, Copy)]
+pub struct TriggerZone {
+    pub min_analog: u8,
+    pub max_analog: u8,
+    pub needs_digital: bool,
+    pub name: &'static str,
+}
 
-    pub fn add_zone(&mut self, zone: &'a dyn ZoneTrait, color: ZoneColor) {
-        self.zones.push((zone, color));
+impl TriggerZone {
+    pub fn in_zone(&self, analog: u8, digital: bool) -> bool {
+        analog >= self.min_analog && analog <= self.max_analog && (digital || !self.needs_digital)
     }
 
-    pub fn default_plane() -> Self {
-        let mut plane = PlaneWithZones::new();
-        plane.add_zone(&DEADZONE, ZoneColor { name: "deadzone".to_string(), bg_color: (0x40, 0x40, 0x40), fg_color: (0xc0, 0xc0, 0xc0) });
-        plane.add_zone(&LEFT_SMASH, ZoneColor { name: "f smash".to_string(), bg_color: (0x40, 0x00, 0x40), fg_color: (0x80, 0x00, 0xff) });
-        plane.add_zone(&RIGHT_SMASH, ZoneColor { name: "f smash".to_string(), bg_color: (0x40, 0x00, 0x40), fg_color: (0x80, 0x00, 0xff) });
-        plane.add_zone(&UP_SMASH, ZoneColor { name: "up smash".to_string(), bg_color: (0x40, 0x00, 0x40), fg_color: (0x80, 0x00, 0xff) });
-        plane.add_zone(&DOWN_SMASH, ZoneColor { name: "d smash".to_string(), bg_color: (0x40, 0x00, 0x40), fg_color: (0x80, 0x00, 0xff) });
-        plane
+    pub fn get_name(&self) -> &'static str {
+        self.name
     }
 }
 
-impl<'a> Plane for PlaneWithZones<'a> {
-    fn get_zone(&self, point: (i8, i8)) -> ZoneColor {
-        for zone in self.zones.iter() {
-            if zone.0.in_zone(point) {
-                return zone.1.clone()
-            }
-        }
-        return ZoneColor { name: "idk lol".to_string(), bg_color: (0x70, 0x70, 0x70), fg_color: WHITE };
-    }
+//analog values are from the trigger's origin
+pub const LIGHTSHIELD: TriggerZone = TriggerZone {
+    min_analog: 43, max_analog: 140, needs_digital: false, name: "lightshield"
+};
+pub const FULL_ANALOG: TriggerZone = TriggerZone {
+    min_analog: 43, max_analog: 255, needs_digital: false, name: "full analog"
+};
+pub const DIGITAL_PRESS: TriggerZone = TriggerZone {
+    min_analog: 0, max_analog: 255, needs_digital: true, name: "digital press"
+};
 
-    fn get_name(&self) -> String {
-        "zones".to_string()
-    }
+pub fn get_some_trigger_zones() -> Vec<TriggerZone> {
+    vec![
+        LIGHTSHIELD,
+        FULL_ANALOG,
+        DIGITAL_PRESS,
+    ]
 }
 
-pub struct Test1 {
+pub fn get_some_zones() -> Vec<Zone> {
+    vec![
+        Zone::SquareZone(DEADZONE),
+        Zone::SquareZone(LEFT_SMASH),
+        Zone::SquareZone(RIGHT_SMASH),
+        f_smash(),
+        Zone::SquareZone(UP_SMASH),
+        Zone::SquareZone(DOWN_SMASH),
+        Zone::Wedge(WAVEDASH_LEFT),
+        Zone::Wedge(WAVEDASH_RIGHT),
+    ]
 }
 
-pub struct Test2 {
-}
+pub trait Plane {
+    fn get_zone(&self, point: (i8, i8)) -> ZoneColor;
+    fn get_name(&self) -> String;
 
-pub struct CStick {
+    /// The zones it's made of, for tracking when the stick goes in and out of them.
+    /// Planes worked out in code don't have any.
+    fn zones(&self) -> Vec<Zone> {
+        vec![]
+    }
 }
 
 const WHITE: (u8, u8, u8) = (0xff, 0xff, 0xff);
 
-impl Plane for Test1 {
-    fn get_zone(&self, point: (i8, i8)) -> ZoneColor {
-        if point.0.abs() < 23 && point.1.abs() < 23 {
-            return ZoneColor { name: "deadzone".to_string(), bg_color: (0x40, 0x40, 0x40), fg_color: (0xc0, 0xc0, 0xc0) };
-        }
-        return ZoneColor { name: "live".to_string(), bg_color: (0x70, 0x70, 0x70), fg_color: WHITE };
+/// What the c stick's plane is called in a planes file.
+pub const C_STICK_PLANE: &str = "c stick";
+
+/// Zones in priority order, a point gets the colour of the first one it's in.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlaneWithZones {
+    pub name: String,
+    pub zones: Vec<(Zone, ZoneColor)>,
+}
+
+impl PlaneWithZones {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), zones: Vec::new() }
     }
 
-    fn get_name(&self) -> String {
-        "test1".to_string()
+    pub fn add_zone(&mut self, zone: Zone, color: ZoneColor) {
+        self.zones.push((zone, color));
     }
-}
 
-impl Plane for Test2 {
-    fn get_zone(&self, point: (i8, i8)) -> ZoneColor {
-        if clamp(point.0, point.1) != point {
-            return ZoneColor { name: "out of bounds".to_string(), bg_color: (0x00, 0x00, 0x00), fg_color: (0x30, 0x00, 0x00) };
-        }
-        if point.0.abs() < 23 && point.1.abs() < 23 {
-            return ZoneColor { name: "deadzone".to_string(), bg_color: (0x40, 0x40, 0x40), fg_color: (0xc0, 0xc0, 0xc0) };
-        }
-        if point.0.abs() >= 64 {
-            return ZoneColor { name: "f smash".to_string(), bg_color: (0x40, 0x00, 0x40), fg_color: (0x80, 0x00, 0xff) };
-        }
-        if point.1 >= 53 {
-            return ZoneColor { name: "up smash".to_string(), bg_color: (0x40, 0x00, 0x40), fg_color: (0x80, 0x00, 0xff) };
-        }
-        if point.1 <= -53 {
-            return ZoneColor { name: "d smash".to_string(), bg_color: (0x40, 0x00, 0x40), fg_color: (0x80, 0x00, 0xff) };
-        }
-        if point.1.abs() < 23 {
-            return ZoneColor { name: "f tilt".to_string(), bg_color: (0x00, 0x00, 0x60), fg_color: (0x00, 0x00, 0xff) };
-        }
-        if point.0.abs() < 23 && point.1 > 0 {
-            return ZoneColor { name: "up tilt".to_string(), bg_color: (0x00, 0x60, 0x00), fg_color: (0x00, 0xff, 0x00) };
-        }
-        if point.0.abs() < 23 && point.1 < 0 {
-            return ZoneColor { name: "d tilt".to_string(), bg_color: (0x60, 0x00, 0x00), fg_color: (0xff, 0x00, 0x00) };
-        }
-        return ZoneColor { name: "idk lol".to_string(), bg_color: (0x70, 0x70, 0x70), fg_color: WHITE };
+    pub fn default_plane() -> Self {
+        let mut plane = PlaneWithZones::new("zones");
+        plane.add_zone(Zone::SquareZone(DEADZONE), ZoneColor { name: "deadzone".to_string(), bg_color: (0x40, 0x40, 0x40), fg_color: (0xc0, 0xc0, 0xc0) });
+        plane.add_zone(f_smash(), ZoneColor { name: "f smash".to_string(), bg_color: (0x40, 0x00, 0x40), fg_color: (0x80, 0x00, 0xff) });
+        plane.add_zone(Zone::SquareZone(UP_SMASH), ZoneColor { name: "up smash".to_string(), bg_color: (0x40, 0x00, 0x40), fg_color: (0x80, 0x00, 0xff) });
+        plane.add_zone(Zone::SquareZone(DOWN_SMASH), ZoneColor { name: "d smash".to_string(), bg_color: (0x40, 0x00, 0x40), fg_color: (0x80, 0x00, 0xff) });
+        plane
     }
 
-    fn get_name(&self) -> String {
-        "test2".to_string()
+    /// Angles instead of smashes, for drilling wavedashes and up b angles.
+    pub fn angle_plane() -> Self {
+        let mut plane = PlaneWithZones::new("angles");
+        plane.add_zone(Zone::Circle(CENTER), ZoneColor { name: "center".to_string(), bg_color: (0x20, 0x20, 0x20), fg_color: (0x80, 0x80, 0x80) });
+        plane.add_zone(Zone::SquareZone(DEADZONE), ZoneColor { name: "deadzone".to_string(), bg_color: (0x40, 0x40, 0x40), fg_color: (0xc0, 0xc0, 0xc0) });
+        plane.add_zone(Zone::Wedge(WAVEDASH_LEFT), ZoneColor { name: "wavedash".to_string(), bg_color: (0x00, 0x40, 0x40), fg_color: (0x00, 0xff, 0xff) });
+        plane.add_zone(Zone::Wedge(WAVEDASH_RIGHT), ZoneColor { name: "wavedash".to_string(), bg_color: (0x00, 0x40, 0x40), fg_color: (0x00, 0xff, 0xff) });
+        plane.add_zone(Zone::Polygon(FIREFOX_UP_RIGHT), ZoneColor { name: "firefox".to_string(), bg_color: (0x40, 0x20, 0x00), fg_color: (0xff, 0x80, 0x00) });
+        plane.add_zone(Zone::Wedge(RIM), ZoneColor { name: "rim".to_string(), bg_color: (0x50, 0x50, 0x50), fg_color: WHITE });
+        plane
+    }
+
+    /// The zones that matter for the c stick, smashes and aerials out of the deadzone.
+    pub fn c_stick_plane() -> Self {
+        let yellow = |name: &str| ZoneColor { name: name.to_string(), bg_color: (0x80, 0x80, 0x00), fg_color: (0xff, 0xff, 0x00) };
+        let mut plane = PlaneWithZones::new(C_STICK_PLANE);
+        let out_of_bounds = Zone::difference("out of bounds", Zone::SquareZone(EVERYTHING), Zone::Circle(Circle { center: (0, 0), radius: 80, name: "out of bounds" }));
+        plane.add_zone(out_of_bounds, ZoneColor { name: "out of bounds".to_string(), bg_color: (0x00, 0x00, 0x00), fg_color: (0x50, 0x00, 0x00) });
+        plane.add_zone(Zone::SquareZone(DEADZONE), ZoneColor { name: "deadzone".to_string(), bg_color: (0x30, 0x30, 0x00), fg_color: (0x70, 0x70, 0x00) });
+        plane.add_zone(f_smash(), yellow("f smash"));
+        plane.add_zone(Zone::SquareZone(UP_SMASH), yellow("up smash"));
+        plane.add_zone(Zone::SquareZone(DOWN_SMASH), yellow("down smash"));
+        //anything else out of the deadzone is an aerial
+        plane.add_zone(Zone::SquareZone(SquareZone { name: "aerial", ..EVERYTHING }), yellow("aerial"));
+        plane
     }
 }
 
-impl Plane for CStick {
+impl Plane for PlaneWithZones {
     fn get_zone(&self, point: (i8, i8)) -> ZoneColor {
-        if clamp(point.0, point.1) != point {
-            return ZoneColor { name: "out of bounds".to_string(), bg_color: (0x00, 0x00, 0x00), fg_color: (0x50, 0x00, 0x00) };
-        }
-        if point.0.abs() < 23 && point.1.abs() < 23 {
-            return ZoneColor { name: "deadzone".to_string(), bg_color: (0x30, 0x30, 0x00), fg_color: (0x70, 0x70, 0x00) };
-        }
-        else if true {
-            return ZoneColor { name: "yellow".to_string(), bg_color: (0x80, 0x80, 0x00), fg_color: (0xff, 0xff, 0x00) };
+        for zone in self.zones.iter() {
+            if zone.0.in_zone(point) {
+                return zone.1.clone()
+            }
         }
         return ZoneColor { name: "idk lol".to_string(), bg_color: (0x70, 0x70, 0x70), fg_color: WHITE };
     }
 
     fn get_name(&self) -> String {
-        "c stick".to_string()
+        self.name.clone()
+    }
+
+    fn zones(&self) -> Vec<Zone> {
+        self.zones.iter().map(|(zone, _)| zone.clone()).collect()
     }
 }